@@ -0,0 +1,297 @@
+use std::cell::RefCell;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+/// Every recorded value points to at most two parents, along with the partial derivative of the
+/// value with respect to each of them.
+#[derive(Clone, Copy)]
+struct Node {
+    parents: [(usize, f64); 2],
+}
+
+pub struct Tape {
+    nodes: RefCell<Vec<Node>>,
+}
+
+#[derive(Clone, Copy)]
+pub struct Var<'t> {
+    tape: &'t Tape,
+    index: usize,
+    value: f64,
+}
+
+pub struct Gradients {
+    adjoints: Vec<f64>,
+}
+
+impl Tape {
+    pub fn new() -> Self {
+        Tape {
+            nodes: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub fn var(&self, value: f64) -> Var<'_> {
+        let index = self.push([(0, 0.0), (0, 0.0)]);
+        Var {
+            tape: self,
+            index,
+            value,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.borrow().is_empty()
+    }
+
+    fn push(&self, parents: [(usize, f64); 2]) -> usize {
+        let mut nodes = self.nodes.borrow_mut();
+        nodes.push(Node { parents });
+        nodes.len() - 1
+    }
+}
+
+impl Default for Tape {
+    fn default() -> Self {
+        Tape::new()
+    }
+}
+
+impl<'t> Var<'t> {
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+
+    /// Records a unary operation whose value and local derivative were computed by the caller.
+    pub fn custom(self, value: f64, derivative: f64) -> Var<'t> {
+        let index = self.tape.push([(self.index, derivative), (0, 0.0)]);
+        Var {
+            tape: self.tape,
+            index,
+            value,
+        }
+    }
+
+    /// Records a binary operation whose value and local derivatives were computed by the caller.
+    pub fn custom_binary(
+        self,
+        other: Var<'t>,
+        value: f64,
+        derivative: f64,
+        other_derivative: f64,
+    ) -> Var<'t> {
+        let index = self
+            .tape
+            .push([(self.index, derivative), (other.index, other_derivative)]);
+        Var {
+            tape: self.tape,
+            index,
+            value,
+        }
+    }
+
+    pub fn exp(self) -> Var<'t> {
+        let exp = self.value.exp();
+        self.custom(exp, exp)
+    }
+
+    pub fn ln(self) -> Var<'t> {
+        self.custom(self.value.ln(), 1.0 / self.value)
+    }
+
+    pub fn ln_1p(self) -> Var<'t> {
+        self.custom(self.value.ln_1p(), 1.0 / (1.0 + self.value))
+    }
+
+    pub fn tanh(self) -> Var<'t> {
+        let tanh = self.value.tanh();
+        self.custom(tanh, 1.0 - tanh * tanh)
+    }
+
+    pub fn sqrt(self) -> Var<'t> {
+        let sqrt = self.value.sqrt();
+        self.custom(sqrt, 0.5 / sqrt)
+    }
+
+    pub fn powi(self, exponent: i32) -> Var<'t> {
+        self.custom(
+            self.value.powi(exponent),
+            exponent as f64 * self.value.powi(exponent - 1),
+        )
+    }
+
+    /// Takes 0 as the derivative at 0, matching `mean_absolute_error_prime`.
+    pub fn abs(self) -> Var<'t> {
+        let derivative = match self.value == 0.0 {
+            true => 0.0,
+            false => self.value.signum(),
+        };
+        self.custom(self.value.abs(), derivative)
+    }
+
+    pub fn max(self, other: f64) -> Var<'t> {
+        match self.value > other {
+            true => self.custom(self.value, 1.0),
+            false => self.custom(other, 0.0),
+        }
+    }
+
+    pub fn min(self, other: f64) -> Var<'t> {
+        match self.value < other {
+            true => self.custom(self.value, 1.0),
+            false => self.custom(other, 0.0),
+        }
+    }
+
+    pub fn backward(&self) -> Gradients {
+        self.backward_with_seed(1.0)
+    }
+
+    /// Propagates `seed`, the gradient of some outer quantity with respect to this value, back
+    /// to every value recorded before it.
+    pub fn backward_with_seed(&self, seed: f64) -> Gradients {
+        let nodes = self.tape.nodes.borrow();
+        let mut adjoints = vec![0.0; nodes.len()];
+        adjoints[self.index] = seed;
+
+        for index in (0..=self.index).rev() {
+            let adjoint = adjoints[index];
+            if adjoint == 0.0 {
+                continue;
+            }
+            for (parent, derivative) in nodes[index].parents {
+                if derivative != 0.0 {
+                    adjoints[parent] += adjoint * derivative;
+                }
+            }
+        }
+
+        Gradients { adjoints }
+    }
+}
+
+impl Gradients {
+    pub fn wrt(&self, var: Var) -> f64 {
+        self.adjoints.get(var.index).cloned().unwrap_or(0.0)
+    }
+}
+
+impl<'t> Add for Var<'t> {
+    type Output = Var<'t>;
+
+    fn add(self, other: Var<'t>) -> Var<'t> {
+        self.custom_binary(other, self.value + other.value, 1.0, 1.0)
+    }
+}
+
+impl<'t> Add<f64> for Var<'t> {
+    type Output = Var<'t>;
+
+    fn add(self, other: f64) -> Var<'t> {
+        self.custom(self.value + other, 1.0)
+    }
+}
+
+impl<'t> Add<Var<'t>> for f64 {
+    type Output = Var<'t>;
+
+    fn add(self, other: Var<'t>) -> Var<'t> {
+        other + self
+    }
+}
+
+impl<'t> Sub for Var<'t> {
+    type Output = Var<'t>;
+
+    fn sub(self, other: Var<'t>) -> Var<'t> {
+        self.custom_binary(other, self.value - other.value, 1.0, -1.0)
+    }
+}
+
+impl<'t> Sub<f64> for Var<'t> {
+    type Output = Var<'t>;
+
+    fn sub(self, other: f64) -> Var<'t> {
+        self.custom(self.value - other, 1.0)
+    }
+}
+
+impl<'t> Sub<Var<'t>> for f64 {
+    type Output = Var<'t>;
+
+    fn sub(self, other: Var<'t>) -> Var<'t> {
+        other.custom(self - other.value, -1.0)
+    }
+}
+
+impl<'t> Mul for Var<'t> {
+    type Output = Var<'t>;
+
+    fn mul(self, other: Var<'t>) -> Var<'t> {
+        self.custom_binary(other, self.value * other.value, other.value, self.value)
+    }
+}
+
+impl<'t> Mul<f64> for Var<'t> {
+    type Output = Var<'t>;
+
+    fn mul(self, other: f64) -> Var<'t> {
+        self.custom(self.value * other, other)
+    }
+}
+
+impl<'t> Mul<Var<'t>> for f64 {
+    type Output = Var<'t>;
+
+    fn mul(self, other: Var<'t>) -> Var<'t> {
+        other * self
+    }
+}
+
+impl<'t> Div for Var<'t> {
+    type Output = Var<'t>;
+
+    fn div(self, other: Var<'t>) -> Var<'t> {
+        self.custom_binary(
+            other,
+            self.value / other.value,
+            1.0 / other.value,
+            -self.value / (other.value * other.value),
+        )
+    }
+}
+
+impl<'t> Div<f64> for Var<'t> {
+    type Output = Var<'t>;
+
+    fn div(self, other: f64) -> Var<'t> {
+        self.custom(self.value / other, 1.0 / other)
+    }
+}
+
+impl<'t> Div<Var<'t>> for f64 {
+    type Output = Var<'t>;
+
+    fn div(self, other: Var<'t>) -> Var<'t> {
+        other.custom(self / other.value, -self / (other.value * other.value))
+    }
+}
+
+impl<'t> Neg for Var<'t> {
+    type Output = Var<'t>;
+
+    fn neg(self) -> Var<'t> {
+        self.custom(-self.value, -1.0)
+    }
+}
+
+/// Value and derivative of a scalar function written in terms of `Var`.
+pub fn derivative(function: for<'t> fn(Var<'t>) -> Var<'t>, value: f64) -> (f64, f64) {
+    let tape = Tape::new();
+    let input = tape.var(value);
+    let output = function(input);
+    (output.value(), output.backward().wrt(input))
+}
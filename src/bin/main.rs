@@ -4,8 +4,7 @@ use std::{
 };
 
 use rusty_network::{
-    functions::activation_functions::{identity, identity_prime},
-    functions::error_functions::squared_loss_prime,
+    functions::activation_functions::ActivationFunction, functions::error_functions::ErrorFunction,
     sequential::network::SequentialNetwork,
 };
 
@@ -48,9 +47,8 @@ fn main() {
         &network_width,
         input_width,
         learning_rate,
-        identity,
-        identity_prime,
-        squared_loss_prime,
+        ActivationFunction::Identity,
+        ErrorFunction::SquaredLoss,
     )
     .unwrap();
 
@@ -65,14 +63,14 @@ fn main() {
 
     //println!("{}", new_network);
 
-    let test_return = new_network.predict_batch_no_activation(&[1.0, 5.09]);
+    let test_return = new_network.predict_batch(&[1.0, 5.09]);
     println!("Retorno de 1 - 5.09: {}", test_return.unwrap());
-    let test_return = new_network.predict_batch_no_activation(&[2.0, 5.09]);
+    let test_return = new_network.predict_batch(&[2.0, 5.09]);
     println!("Retorno de 2 - 10.18: {}", test_return.unwrap());
-    let test_return = new_network.predict_batch_no_activation(&[3.0, 5.09]);
+    let test_return = new_network.predict_batch(&[3.0, 5.09]);
     println!("Retorno de 3 - 15.27: {}", test_return.unwrap());
-    let test_return = new_network.predict_batch_no_activation(&[4.0, 5.09]);
+    let test_return = new_network.predict_batch(&[4.0, 5.09]);
     println!("Retorno de 4 - 20.36: {}", test_return.unwrap());
-    let test_return = new_network.predict_batch_no_activation(&[5.0, 5.09]);
+    let test_return = new_network.predict_batch(&[5.0, 5.09]);
     println!("Retorno de 5 - 25.45: {}", test_return.unwrap());
 }
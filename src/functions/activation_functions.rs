@@ -1,4 +1,300 @@
-pub type ActivationFunctionType = fn(f64) -> f64;
+use std::fmt::{Debug, Display};
+use std::sync::Arc;
+
+use super::parse_parametric_name;
+use crate::autodiff::{derivative, Tape, Var};
+
+pub const DEFAULT_LEAKY_RELU_SLOPE: f64 = 0.01;
+pub const DEFAULT_ELU_ALPHA: f64 = 1.0;
+pub const DEFAULT_PRELU_SLOPE: f64 = 0.25;
+const SELU_LAMBDA: f64 = 1.050_700_987_355_480_5;
+const SELU_ALPHA: f64 = 1.673_263_242_354_377_3;
+const GELU_COEFFICIENT: f64 = 0.044_715;
+const SQRT_TWO_OVER_PI: f64 = 0.797_884_560_802_865_4;
+
+pub trait Activation {
+    fn compute(&self, value: f64) -> f64;
+    fn derivative(&self, value: f64) -> f64;
+    fn name(&self) -> &'static str;
+
+    /// Starting value of the trainable parameter, for activations that learn one.
+    fn initial_parameter(&self) -> Option<f64> {
+        None
+    }
+
+    fn compute_with_parameter(&self, value: f64, _parameter: f64) -> f64 {
+        self.compute(value)
+    }
+
+    fn derivative_with_parameter(&self, value: f64, _parameter: f64) -> f64 {
+        self.derivative(value)
+    }
+
+    /// Derivative of the activation output with respect to its trainable parameter.
+    fn parameter_derivative(&self, _value: f64, _parameter: f64) -> f64 {
+        0.0
+    }
+
+    fn record<'t>(&self, value: Var<'t>) -> Var<'t> {
+        value.custom(self.compute(value.value()), self.derivative(value.value()))
+    }
+
+    fn record_with_parameter<'t>(&self, value: Var<'t>, parameter: Var<'t>) -> Var<'t> {
+        let (input, parameter_value) = (value.value(), parameter.value());
+        value.custom_binary(
+            parameter,
+            self.compute_with_parameter(input, parameter_value),
+            self.derivative_with_parameter(input, parameter_value),
+            self.parameter_derivative(input, parameter_value),
+        )
+    }
+}
+
+pub type AutodiffFunctionType = for<'t> fn(Var<'t>) -> Var<'t>;
+
+/// An activation written once in terms of `Var`, with its derivative taken by the autodiff tape.
+#[derive(Clone, Copy, Debug)]
+pub struct AutodiffActivation {
+    pub name: &'static str,
+    pub function: AutodiffFunctionType,
+}
+
+impl AutodiffActivation {
+    pub fn new(name: &'static str, function: AutodiffFunctionType) -> Self {
+        AutodiffActivation { name, function }
+    }
+}
+
+impl Activation for AutodiffActivation {
+    fn compute(&self, value: f64) -> f64 {
+        let tape = Tape::new();
+        (self.function)(tape.var(value)).value()
+    }
+
+    fn derivative(&self, value: f64) -> f64 {
+        derivative(self.function, value).1
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn record<'t>(&self, value: Var<'t>) -> Var<'t> {
+        (self.function)(value)
+    }
+}
+
+impl PartialEq for AutodiffActivation {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::fn_addr_eq(self.function, other.function)
+    }
+}
+
+pub type ActivationClosureType = Arc<dyn Fn(f64) -> f64 + Send + Sync>;
+
+/// An activation built from a pair of closures.
+#[derive(Clone)]
+pub struct ClosureActivation {
+    pub name: &'static str,
+    compute: ActivationClosureType,
+    derivative: ActivationClosureType,
+}
+
+impl ClosureActivation {
+    pub fn new(
+        name: &'static str,
+        compute: impl Fn(f64) -> f64 + Send + Sync + 'static,
+        derivative: impl Fn(f64) -> f64 + Send + Sync + 'static,
+    ) -> Self {
+        ClosureActivation {
+            name,
+            compute: Arc::new(compute),
+            derivative: Arc::new(derivative),
+        }
+    }
+}
+
+impl Activation for ClosureActivation {
+    fn compute(&self, value: f64) -> f64 {
+        (self.compute)(value)
+    }
+
+    fn derivative(&self, value: f64) -> f64 {
+        (self.derivative)(value)
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+impl PartialEq for ClosureActivation {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.compute, &other.compute)
+            && Arc::ptr_eq(&self.derivative, &other.derivative)
+    }
+}
+
+impl Debug for ClosureActivation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClosureActivation")
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ActivationFunction {
+    Relu,
+    Sigmoid,
+    Identity,
+    Tanh,
+    LeakyRelu(f64),
+    Elu(f64),
+    Selu,
+    Gelu,
+    Swish,
+    Softplus,
+    Softsign,
+    PRelu(f64),
+    Autodiff(AutodiffActivation),
+    Closure(ClosureActivation),
+}
+
+impl ActivationFunction {
+    /// Takes a name or the `Display` form, e.g. `leaky_relu(0.2)`. A bare parametric name gets
+    /// the default parameter.
+    pub fn from_name(name: &str) -> Option<ActivationFunction> {
+        let (name, parameters) = parse_parametric_name(name)?;
+        match (name, parameters.as_slice()) {
+            ("relu", []) => Some(ActivationFunction::Relu),
+            ("sigmoid", []) => Some(ActivationFunction::Sigmoid),
+            ("identity", []) => Some(ActivationFunction::Identity),
+            ("tanh", []) => Some(ActivationFunction::Tanh),
+            ("leaky_relu", []) => Some(ActivationFunction::LeakyRelu(DEFAULT_LEAKY_RELU_SLOPE)),
+            ("leaky_relu", [slope]) => Some(ActivationFunction::LeakyRelu(*slope)),
+            ("elu", []) => Some(ActivationFunction::Elu(DEFAULT_ELU_ALPHA)),
+            ("elu", [alpha]) => Some(ActivationFunction::Elu(*alpha)),
+            ("selu", []) => Some(ActivationFunction::Selu),
+            ("gelu", []) => Some(ActivationFunction::Gelu),
+            ("swish", []) => Some(ActivationFunction::Swish),
+            ("softplus", []) => Some(ActivationFunction::Softplus),
+            ("softsign", []) => Some(ActivationFunction::Softsign),
+            ("prelu", []) => Some(ActivationFunction::PRelu(DEFAULT_PRELU_SLOPE)),
+            ("prelu", [slope]) => Some(ActivationFunction::PRelu(*slope)),
+            _ => None,
+        }
+    }
+
+    pub fn from_closures(
+        name: &'static str,
+        compute: impl Fn(f64) -> f64 + Send + Sync + 'static,
+        derivative: impl Fn(f64) -> f64 + Send + Sync + 'static,
+    ) -> ActivationFunction {
+        ActivationFunction::Closure(ClosureActivation::new(name, compute, derivative))
+    }
+}
+
+impl Activation for ActivationFunction {
+    fn compute(&self, value: f64) -> f64 {
+        match self {
+            ActivationFunction::Relu => relu(value),
+            ActivationFunction::Sigmoid => sigmoid(value),
+            ActivationFunction::Identity => identity(value),
+            ActivationFunction::Tanh => tanh(value),
+            ActivationFunction::LeakyRelu(slope) => leaky_relu(value, *slope),
+            ActivationFunction::Elu(alpha) => elu(value, *alpha),
+            ActivationFunction::Selu => selu(value),
+            ActivationFunction::Gelu => gelu(value),
+            ActivationFunction::Swish => swish(value),
+            ActivationFunction::Softplus => softplus(value),
+            ActivationFunction::Softsign => softsign(value),
+            ActivationFunction::PRelu(slope) => leaky_relu(value, *slope),
+            ActivationFunction::Autodiff(activation) => activation.compute(value),
+            ActivationFunction::Closure(activation) => activation.compute(value),
+        }
+    }
+
+    fn derivative(&self, value: f64) -> f64 {
+        match self {
+            ActivationFunction::Relu => relu_prime(value),
+            ActivationFunction::Sigmoid => sigmoid_prime(value),
+            ActivationFunction::Identity => identity_prime(value),
+            ActivationFunction::Tanh => tanh_prime(value),
+            ActivationFunction::LeakyRelu(slope) => leaky_relu_prime(value, *slope),
+            ActivationFunction::Elu(alpha) => elu_prime(value, *alpha),
+            ActivationFunction::Selu => selu_prime(value),
+            ActivationFunction::Gelu => gelu_prime(value),
+            ActivationFunction::Swish => swish_prime(value),
+            ActivationFunction::Softplus => softplus_prime(value),
+            ActivationFunction::Softsign => softsign_prime(value),
+            ActivationFunction::PRelu(slope) => leaky_relu_prime(value, *slope),
+            ActivationFunction::Autodiff(activation) => activation.derivative(value),
+            ActivationFunction::Closure(activation) => activation.derivative(value),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            ActivationFunction::Relu => "relu",
+            ActivationFunction::Sigmoid => "sigmoid",
+            ActivationFunction::Identity => "identity",
+            ActivationFunction::Tanh => "tanh",
+            ActivationFunction::LeakyRelu(_) => "leaky_relu",
+            ActivationFunction::Elu(_) => "elu",
+            ActivationFunction::Selu => "selu",
+            ActivationFunction::Gelu => "gelu",
+            ActivationFunction::Swish => "swish",
+            ActivationFunction::Softplus => "softplus",
+            ActivationFunction::Softsign => "softsign",
+            ActivationFunction::PRelu(_) => "prelu",
+            ActivationFunction::Autodiff(activation) => activation.name(),
+            ActivationFunction::Closure(activation) => activation.name(),
+        }
+    }
+
+    fn initial_parameter(&self) -> Option<f64> {
+        match self {
+            ActivationFunction::PRelu(slope) => Some(*slope),
+            _ => None,
+        }
+    }
+
+    fn compute_with_parameter(&self, value: f64, parameter: f64) -> f64 {
+        match self {
+            ActivationFunction::PRelu(_) => leaky_relu(value, parameter),
+            _ => self.compute(value),
+        }
+    }
+
+    fn derivative_with_parameter(&self, value: f64, parameter: f64) -> f64 {
+        match self {
+            ActivationFunction::PRelu(_) => leaky_relu_prime(value, parameter),
+            _ => self.derivative(value),
+        }
+    }
+
+    fn parameter_derivative(&self, value: f64, _parameter: f64) -> f64 {
+        match self {
+            ActivationFunction::PRelu(_) => prelu_slope_derivative(value),
+            _ => 0.0,
+        }
+    }
+}
+
+impl Display for ActivationFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ActivationFunction::LeakyRelu(parameter)
+            | ActivationFunction::Elu(parameter)
+            | ActivationFunction::PRelu(parameter) => {
+                write!(f, "{}({})", self.name(), parameter)
+            }
+            _ => write!(f, "{}", self.name()),
+        }
+    }
+}
+
 pub fn relu(value: f64) -> f64 {
     match value > 0.0 {
         true => value,
@@ -21,10 +317,149 @@ pub fn identity_prime(_value: f64) -> f64 {
 }
 
 pub fn sigmoid(input: f64) -> f64 {
-    // Returns sigmoid of the input
-    1.0 / (1.0 + (-input).exp())
+    // Both branches only ever exponentiate a non-positive number, so neither overflows.
+    match input >= 0.0 {
+        true => 1.0 / (1.0 + (-input).exp()),
+        false => {
+            let exp = input.exp();
+            exp / (1.0 + exp)
+        }
+    }
 }
 
 pub fn sigmoid_prime(input: f64) -> f64 {
-    sigmoid(input) * (1.0 - sigmoid(input))
+    let sigmoid = sigmoid(input);
+    sigmoid * (1.0 - sigmoid)
+}
+
+pub fn tanh(value: f64) -> f64 {
+    value.tanh()
+}
+
+pub fn tanh_prime(value: f64) -> f64 {
+    let tanh = value.tanh();
+    1.0 - tanh * tanh
+}
+
+pub fn leaky_relu(value: f64, slope: f64) -> f64 {
+    match value > 0.0 {
+        true => value,
+        false => slope * value,
+    }
+}
+
+pub fn leaky_relu_prime(value: f64, slope: f64) -> f64 {
+    match value > 0.0 {
+        true => 1.0,
+        false => slope,
+    }
+}
+
+pub fn prelu_slope_derivative(value: f64) -> f64 {
+    match value > 0.0 {
+        true => 0.0,
+        false => value,
+    }
+}
+
+pub fn elu(value: f64, alpha: f64) -> f64 {
+    match value > 0.0 {
+        true => value,
+        false => alpha * value.exp_m1(),
+    }
+}
+
+pub fn elu_prime(value: f64, alpha: f64) -> f64 {
+    match value > 0.0 {
+        true => 1.0,
+        false => alpha * value.exp(),
+    }
+}
+
+pub fn selu(value: f64) -> f64 {
+    SELU_LAMBDA * elu(value, SELU_ALPHA)
+}
+
+pub fn selu_prime(value: f64) -> f64 {
+    SELU_LAMBDA * elu_prime(value, SELU_ALPHA)
+}
+
+// Tanh approximation of GELU, since the standard library has no erf.
+pub fn gelu(value: f64) -> f64 {
+    let inner = SQRT_TWO_OVER_PI * (value + GELU_COEFFICIENT * value.powi(3));
+    0.5 * value * (1.0 + inner.tanh())
+}
+
+pub fn gelu_prime(value: f64) -> f64 {
+    let inner = SQRT_TWO_OVER_PI * (value + GELU_COEFFICIENT * value.powi(3));
+    let tanh = inner.tanh();
+    let inner_prime = SQRT_TWO_OVER_PI * (1.0 + 3.0 * GELU_COEFFICIENT * value * value);
+    0.5 * (1.0 + tanh) + 0.5 * value * (1.0 - tanh * tanh) * inner_prime
+}
+
+pub fn swish(value: f64) -> f64 {
+    value * sigmoid(value)
+}
+
+pub fn swish_prime(value: f64) -> f64 {
+    let sigmoid = sigmoid(value);
+    sigmoid + value * sigmoid * (1.0 - sigmoid)
+}
+
+pub fn softplus(value: f64) -> f64 {
+    // log(1 + e^x) rewritten as max(x, 0) + log(1 + e^-|x|) to avoid overflow.
+    value.max(0.0) + (-value.abs()).exp().ln_1p()
+}
+
+pub fn softplus_prime(value: f64) -> f64 {
+    sigmoid(value)
+}
+
+pub fn softsign(value: f64) -> f64 {
+    value / (1.0 + value.abs())
+}
+
+pub fn softsign_prime(value: f64) -> f64 {
+    let denominator = 1.0 + value.abs();
+    1.0 / (denominator * denominator)
+}
+
+pub fn softmax(values: &[f64]) -> Vec<f64> {
+    // Shifting by the maximum leaves the result unchanged and keeps every exponent <= 0.
+    let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let exponentials = values
+        .iter()
+        .map(|value| (value - max).exp())
+        .collect::<Vec<f64>>();
+    let sum = exponentials.iter().sum::<f64>();
+    exponentials.into_iter().map(|value| value / sum).collect()
+}
+
+pub fn softmax_jacobian(probabilities: &[f64]) -> Vec<Vec<f64>> {
+    probabilities
+        .iter()
+        .enumerate()
+        .map(|(i, p_i)| {
+            probabilities
+                .iter()
+                .enumerate()
+                .map(|(j, p_j)| match i == j {
+                    true => p_i * (1.0 - p_j),
+                    false => -p_i * p_j,
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Propagates a gradient taken with respect to the softmax probabilities back to the logits.
+pub fn softmax_backward(probabilities: &[f64], output_gradient: &[f64]) -> Vec<f64> {
+    softmax_jacobian(probabilities)
+        .iter()
+        .map(|row| {
+            row.iter()
+                .zip(output_gradient)
+                .fold(0.0, |acc, (jacobian, gradient)| acc + jacobian * gradient)
+        })
+        .collect()
 }
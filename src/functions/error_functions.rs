@@ -1,4 +1,447 @@
-pub type ErrorFunctionType = fn(f64, f64) -> f64;
+use std::fmt::{Debug, Display};
+use std::sync::Arc;
+
+use super::activation_functions::{sigmoid, softmax};
+use super::parse_parametric_name;
+use crate::autodiff::{Tape, Var};
+use crate::network_model::NetworkError;
+
+/// Probabilities are clamped this far away from 0 and 1 before taking logarithms.
+pub const PROBABILITY_EPSILON: f64 = 1e-12;
+pub const DEFAULT_HUBER_DELTA: f64 = 1.0;
+pub const DEFAULT_PINBALL_QUANTILE: f64 = 0.5;
+pub const DEFAULT_FOCAL_GAMMA: f64 = 2.0;
+pub const DEFAULT_FOCAL_ALPHA: f64 = 0.25;
+
+pub trait Loss {
+    fn value(&self, aim: f64, final_answer: f64) -> f64;
+    fn gradient(&self, aim: f64, final_answer: f64) -> f64;
+    fn name(&self) -> &'static str;
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ErrorFunction {
+    SquaredLoss,
+    /// Expects probabilities, e.g. from a sigmoid output activation.
+    BinaryCrossEntropy,
+    /// Expects raw logits, e.g. from an identity output activation.
+    BinaryCrossEntropyWithLogits,
+    /// Expects probabilities and one-hot aims, summed over the output vector.
+    CategoricalCrossEntropy,
+    /// Quadratic for residuals up to delta, linear beyond it.
+    Huber(f64),
+    MeanAbsoluteError,
+    LogCosh,
+    /// Expects raw margins and aims of -1 or 1.
+    Hinge,
+    /// Expects raw margins and aims of -1 or 1.
+    SquaredHinge,
+    /// Quantile loss for the given quantile in (0, 1); 0.5 fits the median.
+    Pinball(f64),
+    /// Expects probabilities. Down-weights well classified samples by `(1 - p_t)^gamma` and
+    /// weighs positives by `alpha` and negatives by `1 - alpha`.
+    Focal {
+        gamma: f64,
+        alpha: f64,
+    },
+    Autodiff(AutodiffLoss),
+    Closure(ClosureLoss),
+}
+
+impl ErrorFunction {
+    /// Takes a name or the `Display` form, e.g. `huber(0.5)`. A bare parametric name gets the
+    /// default parameters. A Huber delta must be finite and positive.
+    pub fn from_name(name: &str) -> Option<ErrorFunction> {
+        let (name, parameters) = parse_parametric_name(name)?;
+        match (name, parameters.as_slice()) {
+            ("squared_loss", []) => Some(ErrorFunction::SquaredLoss),
+            ("binary_cross_entropy", []) => Some(ErrorFunction::BinaryCrossEntropy),
+            ("binary_cross_entropy_with_logits", []) => {
+                Some(ErrorFunction::BinaryCrossEntropyWithLogits)
+            }
+            ("categorical_cross_entropy", []) => Some(ErrorFunction::CategoricalCrossEntropy),
+            ("huber", []) => Some(ErrorFunction::Huber(DEFAULT_HUBER_DELTA)),
+            ("huber", [delta]) if is_valid_huber_delta(*delta) => {
+                Some(ErrorFunction::Huber(*delta))
+            }
+            ("mean_absolute_error", []) => Some(ErrorFunction::MeanAbsoluteError),
+            ("log_cosh", []) => Some(ErrorFunction::LogCosh),
+            ("hinge", []) => Some(ErrorFunction::Hinge),
+            ("squared_hinge", []) => Some(ErrorFunction::SquaredHinge),
+            ("pinball", []) => Some(ErrorFunction::Pinball(DEFAULT_PINBALL_QUANTILE)),
+            ("pinball", [quantile]) => Some(ErrorFunction::Pinball(*quantile)),
+            ("focal", []) => Some(ErrorFunction::Focal {
+                gamma: DEFAULT_FOCAL_GAMMA,
+                alpha: DEFAULT_FOCAL_ALPHA,
+            }),
+            ("focal", [gamma, alpha]) => Some(ErrorFunction::Focal {
+                gamma: *gamma,
+                alpha: *alpha,
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn from_closures(
+        name: &'static str,
+        value: impl Fn(f64, f64) -> f64 + Send + Sync + 'static,
+        gradient: impl Fn(f64, f64) -> f64 + Send + Sync + 'static,
+    ) -> ErrorFunction {
+        ErrorFunction::Closure(ClosureLoss::new(name, value, gradient))
+    }
+
+    pub fn validate(&self) -> Result<(), NetworkError> {
+        match self {
+            ErrorFunction::Huber(delta) if !is_valid_huber_delta(*delta) => {
+                Err(NetworkError::InvalidHuberDelta(*delta))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+fn is_valid_huber_delta(delta: f64) -> bool {
+    delta.is_finite() && delta > 0.0
+}
+
+impl Loss for ErrorFunction {
+    fn value(&self, aim: f64, final_answer: f64) -> f64 {
+        match self {
+            ErrorFunction::SquaredLoss => squared_loss(aim, final_answer),
+            ErrorFunction::BinaryCrossEntropy => binary_cross_entropy(aim, final_answer),
+            ErrorFunction::BinaryCrossEntropyWithLogits => {
+                binary_cross_entropy_with_logits(aim, final_answer)
+            }
+            ErrorFunction::CategoricalCrossEntropy => categorical_cross_entropy(aim, final_answer),
+            ErrorFunction::Huber(delta) => huber(aim, final_answer, *delta),
+            ErrorFunction::MeanAbsoluteError => mean_absolute_error(aim, final_answer),
+            ErrorFunction::LogCosh => log_cosh(aim, final_answer),
+            ErrorFunction::Hinge => hinge(aim, final_answer),
+            ErrorFunction::SquaredHinge => squared_hinge(aim, final_answer),
+            ErrorFunction::Pinball(quantile) => pinball(aim, final_answer, *quantile),
+            ErrorFunction::Focal { gamma, alpha } => focal(aim, final_answer, *gamma, *alpha),
+            ErrorFunction::Autodiff(loss) => loss.value(aim, final_answer),
+            ErrorFunction::Closure(loss) => loss.value(aim, final_answer),
+        }
+    }
+
+    fn gradient(&self, aim: f64, final_answer: f64) -> f64 {
+        match self {
+            ErrorFunction::SquaredLoss => squared_loss_prime(aim, final_answer),
+            ErrorFunction::BinaryCrossEntropy => binary_cross_entropy_prime(aim, final_answer),
+            ErrorFunction::BinaryCrossEntropyWithLogits => {
+                binary_cross_entropy_with_logits_prime(aim, final_answer)
+            }
+            ErrorFunction::CategoricalCrossEntropy => {
+                categorical_cross_entropy_prime(aim, final_answer)
+            }
+            ErrorFunction::Huber(delta) => huber_prime(aim, final_answer, *delta),
+            ErrorFunction::MeanAbsoluteError => mean_absolute_error_prime(aim, final_answer),
+            ErrorFunction::LogCosh => log_cosh_prime(aim, final_answer),
+            ErrorFunction::Hinge => hinge_prime(aim, final_answer),
+            ErrorFunction::SquaredHinge => squared_hinge_prime(aim, final_answer),
+            ErrorFunction::Pinball(quantile) => pinball_prime(aim, final_answer, *quantile),
+            ErrorFunction::Focal { gamma, alpha } => focal_prime(aim, final_answer, *gamma, *alpha),
+            ErrorFunction::Autodiff(loss) => loss.gradient(aim, final_answer),
+            ErrorFunction::Closure(loss) => loss.gradient(aim, final_answer),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            ErrorFunction::SquaredLoss => "squared_loss",
+            ErrorFunction::BinaryCrossEntropy => "binary_cross_entropy",
+            ErrorFunction::BinaryCrossEntropyWithLogits => "binary_cross_entropy_with_logits",
+            ErrorFunction::CategoricalCrossEntropy => "categorical_cross_entropy",
+            ErrorFunction::Huber(_) => "huber",
+            ErrorFunction::MeanAbsoluteError => "mean_absolute_error",
+            ErrorFunction::LogCosh => "log_cosh",
+            ErrorFunction::Hinge => "hinge",
+            ErrorFunction::SquaredHinge => "squared_hinge",
+            ErrorFunction::Pinball(_) => "pinball",
+            ErrorFunction::Focal { .. } => "focal",
+            ErrorFunction::Autodiff(loss) => loss.name(),
+            ErrorFunction::Closure(loss) => loss.name(),
+        }
+    }
+}
+
+impl Display for ErrorFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ErrorFunction::Huber(delta) => write!(f, "{}({})", self.name(), delta),
+            ErrorFunction::Pinball(quantile) => write!(f, "{}({})", self.name(), quantile),
+            ErrorFunction::Focal { gamma, alpha } => {
+                write!(f, "{}({}, {})", self.name(), gamma, alpha)
+            }
+            _ => write!(f, "{}", self.name()),
+        }
+    }
+}
+
+pub type AutodiffLossType = for<'t> fn(Var<'t>, Var<'t>) -> Var<'t>;
+
+/// A loss written once in terms of `Var`, with its gradient taken by the autodiff tape.
+#[derive(Clone, Copy, Debug)]
+pub struct AutodiffLoss {
+    pub name: &'static str,
+    pub loss: AutodiffLossType,
+}
+
+impl AutodiffLoss {
+    pub fn new(name: &'static str, loss: AutodiffLossType) -> Self {
+        AutodiffLoss { name, loss }
+    }
+}
+
+impl Loss for AutodiffLoss {
+    fn value(&self, aim: f64, final_answer: f64) -> f64 {
+        let tape = Tape::new();
+        (self.loss)(tape.var(aim), tape.var(final_answer)).value()
+    }
+
+    fn gradient(&self, aim: f64, final_answer: f64) -> f64 {
+        autodiff_loss_prime(self.loss, aim, final_answer)
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+impl PartialEq for AutodiffLoss {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::fn_addr_eq(self.loss, other.loss)
+    }
+}
+
+pub type LossClosureType = Arc<dyn Fn(f64, f64) -> f64 + Send + Sync>;
+
+/// A loss built from a pair of closures taking `(aim, final_answer)`.
+#[derive(Clone)]
+pub struct ClosureLoss {
+    pub name: &'static str,
+    value: LossClosureType,
+    gradient: LossClosureType,
+}
+
+impl ClosureLoss {
+    pub fn new(
+        name: &'static str,
+        value: impl Fn(f64, f64) -> f64 + Send + Sync + 'static,
+        gradient: impl Fn(f64, f64) -> f64 + Send + Sync + 'static,
+    ) -> Self {
+        ClosureLoss {
+            name,
+            value: Arc::new(value),
+            gradient: Arc::new(gradient),
+        }
+    }
+}
+
+impl Loss for ClosureLoss {
+    fn value(&self, aim: f64, final_answer: f64) -> f64 {
+        (self.value)(aim, final_answer)
+    }
+
+    fn gradient(&self, aim: f64, final_answer: f64) -> f64 {
+        (self.gradient)(aim, final_answer)
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+impl PartialEq for ClosureLoss {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.value, &other.value) && Arc::ptr_eq(&self.gradient, &other.gradient)
+    }
+}
+
+impl Debug for ClosureLoss {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClosureLoss")
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+pub fn squared_loss(aim: f64, final_answer: f64) -> f64 {
+    (aim - final_answer).powi(2)
+}
+
 pub fn squared_loss_prime(aim: f64, final_answer: f64) -> f64 {
     -2.0 * (aim - final_answer)
 }
+
+fn clamp_probability(probability: f64) -> f64 {
+    probability.clamp(PROBABILITY_EPSILON, 1.0 - PROBABILITY_EPSILON)
+}
+
+pub fn binary_cross_entropy(aim: f64, final_answer: f64) -> f64 {
+    let probability = clamp_probability(final_answer);
+    -(aim * probability.ln() + (1.0 - aim) * (1.0 - probability).ln())
+}
+
+pub fn binary_cross_entropy_prime(aim: f64, final_answer: f64) -> f64 {
+    let probability = clamp_probability(final_answer);
+    (probability - aim) / (probability * (1.0 - probability))
+}
+
+pub fn binary_cross_entropy_with_logits(aim: f64, final_answer: f64) -> f64 {
+    // log(1 + e^-|x|) keeps the exponent non-positive for either sign of the logit.
+    final_answer.max(0.0) - final_answer * aim + (-final_answer.abs()).exp().ln_1p()
+}
+
+pub fn binary_cross_entropy_with_logits_prime(aim: f64, final_answer: f64) -> f64 {
+    sigmoid(final_answer) - aim
+}
+
+pub fn categorical_cross_entropy(aim: f64, final_answer: f64) -> f64 {
+    -aim * clamp_probability(final_answer).ln()
+}
+
+pub fn categorical_cross_entropy_prime(aim: f64, final_answer: f64) -> f64 {
+    -aim / clamp_probability(final_answer)
+}
+
+pub fn huber(aim: f64, final_answer: f64, delta: f64) -> f64 {
+    let residual = (final_answer - aim).abs();
+    match residual <= delta {
+        true => 0.5 * residual * residual,
+        false => delta * (residual - 0.5 * delta),
+    }
+}
+
+pub fn huber_prime(aim: f64, final_answer: f64, delta: f64) -> f64 {
+    (final_answer - aim).clamp(-delta, delta)
+}
+
+pub fn mean_absolute_error(aim: f64, final_answer: f64) -> f64 {
+    (final_answer - aim).abs()
+}
+
+/// Subgradient of the absolute error, taken as 0 when the answer hits the aim exactly.
+pub fn mean_absolute_error_prime(aim: f64, final_answer: f64) -> f64 {
+    let residual = final_answer - aim;
+    match residual == 0.0 {
+        true => 0.0,
+        false => residual.signum(),
+    }
+}
+
+pub fn log_cosh(aim: f64, final_answer: f64) -> f64 {
+    // log(cosh(x)) = |x| + log(1 + e^(-2|x|)) - log(2), which cannot overflow for large |x|.
+    let residual = (final_answer - aim).abs();
+    residual + (-2.0 * residual).exp().ln_1p() - std::f64::consts::LN_2
+}
+
+pub fn log_cosh_prime(aim: f64, final_answer: f64) -> f64 {
+    (final_answer - aim).tanh()
+}
+
+pub fn hinge(aim: f64, final_answer: f64) -> f64 {
+    (1.0 - aim * final_answer).max(0.0)
+}
+
+/// Subgradient of the hinge loss, taken as 0 exactly on the margin.
+pub fn hinge_prime(aim: f64, final_answer: f64) -> f64 {
+    match aim * final_answer < 1.0 {
+        true => -aim,
+        false => 0.0,
+    }
+}
+
+pub fn squared_hinge(aim: f64, final_answer: f64) -> f64 {
+    hinge(aim, final_answer).powi(2)
+}
+
+pub fn squared_hinge_prime(aim: f64, final_answer: f64) -> f64 {
+    -2.0 * aim * hinge(aim, final_answer)
+}
+
+pub fn pinball(aim: f64, final_answer: f64, quantile: f64) -> f64 {
+    let residual = aim - final_answer;
+    (quantile * residual).max((quantile - 1.0) * residual)
+}
+
+/// Subgradient of the pinball loss, taken as 0 when the answer hits the aim exactly.
+pub fn pinball_prime(aim: f64, final_answer: f64, quantile: f64) -> f64 {
+    let residual = aim - final_answer;
+    match residual {
+        r if r > 0.0 => -quantile,
+        r if r < 0.0 => 1.0 - quantile,
+        _ => 0.0,
+    }
+}
+
+pub fn focal(aim: f64, final_answer: f64, gamma: f64, alpha: f64) -> f64 {
+    let probability = clamp_probability(final_answer);
+    -alpha * aim * (1.0 - probability).powf(gamma) * probability.ln()
+        - (1.0 - alpha) * (1.0 - aim) * probability.powf(gamma) * (1.0 - probability).ln()
+}
+
+pub fn focal_prime(aim: f64, final_answer: f64, gamma: f64, alpha: f64) -> f64 {
+    let probability = clamp_probability(final_answer);
+    let positive = -gamma * (1.0 - probability).powf(gamma - 1.0) * probability.ln()
+        + (1.0 - probability).powf(gamma) / probability;
+    let negative = gamma * probability.powf(gamma - 1.0) * (1.0 - probability).ln()
+        - probability.powf(gamma) / (1.0 - probability);
+    -alpha * aim * positive - (1.0 - alpha) * (1.0 - aim) * negative
+}
+
+/// Gradient with respect to `final_answer` of a loss written in terms of `Var`.
+pub fn autodiff_loss_prime(loss: AutodiffLossType, aim: f64, final_answer: f64) -> f64 {
+    let tape = Tape::new();
+    let aim = tape.var(aim);
+    let final_answer = tape.var(final_answer);
+    loss(aim, final_answer).backward().wrt(final_answer)
+}
+
+/// Cross-entropy over softmax(logits), computed through log-softmax so no probability underflows
+/// into a logarithm of zero.
+pub fn softmax_cross_entropy(aims: &[f64], logits: &[f64]) -> f64 {
+    let max = logits.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let log_sum_exp = max
+        + logits
+            .iter()
+            .map(|logit| (logit - max).exp())
+            .sum::<f64>()
+            .ln();
+    aims.iter()
+        .zip(logits)
+        .map(|(aim, logit)| -aim * (logit - log_sum_exp))
+        .sum()
+}
+
+/// Gradient of cross-entropy over softmax(logits), taken directly with respect to the logits.
+pub fn softmax_cross_entropy_prime(aims: &[f64], logits: &[f64]) -> Vec<f64> {
+    softmax(logits)
+        .into_iter()
+        .zip(aims)
+        .map(|(probability, aim)| probability - aim)
+        .collect()
+}
+
+/// Negative log-likelihood of `aim` under a normal distribution with the given mean and
+/// log-variance.
+pub fn gaussian_negative_log_likelihood(aim: f64, mean: f64, log_variance: f64) -> f64 {
+    0.5 * ((2.0 * std::f64::consts::PI).ln()
+        + log_variance
+        + (aim - mean).powi(2) * (-log_variance).exp())
+}
+
+/// Gradient of the Gaussian negative log-likelihood with respect to the mean and the
+/// log-variance, in that order.
+pub fn gaussian_negative_log_likelihood_prime(
+    aim: f64,
+    mean: f64,
+    log_variance: f64,
+) -> (f64, f64) {
+    let precision = (-log_variance).exp();
+    (
+        -(aim - mean) * precision,
+        0.5 * (1.0 - (aim - mean).powi(2) * precision),
+    )
+}
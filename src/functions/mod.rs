@@ -1,2 +1,21 @@
+//! Activations, losses and initializers can also be built from closures, which may capture
+//! values chosen at runtime, or from functions on autodiff `Var`s. Neither can be inspected, so
+//! two such values are equal only when they share the same closures or function.
+
 pub mod activation_functions;
 pub mod error_functions;
+pub mod weight_constraints;
+pub mod weight_initializers;
+
+/// Splits a `Display` name such as `focal(2, 0.25)` into its base name and parameters. A bare
+/// name has no parameters.
+pub(crate) fn parse_parametric_name(name: &str) -> Option<(&str, Vec<f64>)> {
+    match name.strip_suffix(')').and_then(|name| name.split_once('(')) {
+        Some((base, parameters)) => parameters
+            .split(',')
+            .map(|parameter| parameter.trim().parse::<f64>().ok())
+            .collect::<Option<Vec<f64>>>()
+            .map(|parameters| (base, parameters)),
+        None => Some((name, Vec::new())),
+    }
+}
@@ -0,0 +1,57 @@
+use crate::network_model::NetworkError;
+
+/// A bound on each neuron's incoming weights, re-applied after every update. Biases are left
+/// free.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WeightConstraint {
+    /// Rescales the weights whenever their euclidean norm exceeds the given value.
+    MaxNorm(f64),
+    NonNegative,
+    Clip {
+        min: f64,
+        max: f64,
+    },
+}
+
+impl WeightConstraint {
+    /// Clip bounds must be ordered and a max norm finite and non-negative, NaN failing both.
+    pub fn validate(&self) -> Result<(), NetworkError> {
+        let valid = match self {
+            WeightConstraint::MaxNorm(max_norm) => max_norm.is_finite() && *max_norm >= 0.0,
+            WeightConstraint::NonNegative => true,
+            WeightConstraint::Clip { min, max } => min <= max,
+        };
+        match valid {
+            true => Ok(()),
+            false => Err(NetworkError::InvalidWeightConstraint(*self)),
+        }
+    }
+
+    pub fn apply(&self, weights: &mut [f64]) {
+        match self {
+            WeightConstraint::MaxNorm(max_norm) => {
+                let norm = weights
+                    .iter()
+                    .map(|weight| weight * weight)
+                    .sum::<f64>()
+                    .sqrt();
+                if norm > *max_norm {
+                    let scale = max_norm / norm;
+                    for weight in weights.iter_mut() {
+                        *weight *= scale;
+                    }
+                }
+            }
+            WeightConstraint::NonNegative => {
+                for weight in weights.iter_mut() {
+                    *weight = weight.max(0.0);
+                }
+            }
+            WeightConstraint::Clip { min, max } => {
+                for weight in weights.iter_mut() {
+                    *weight = weight.clamp(*min, *max);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use std::f64::consts::PI;
+use std::fmt::{Debug, Display};
+use std::sync::Arc;
+
+use rand::{Rng, RngCore};
+
+use super::parse_parametric_name;
+use crate::vector_math::dot_scalar;
+
+/// Draws one weight from the rng, given the fan-in and fan-out of its layer.
+pub type InitializerClosureType = Arc<dyn Fn(&mut dyn RngCore, usize, usize) -> f64 + Send + Sync>;
+
+#[derive(Clone)]
+pub struct ClosureInitializer {
+    pub name: &'static str,
+    weight: InitializerClosureType,
+}
+
+impl ClosureInitializer {
+    pub fn new(
+        name: &'static str,
+        weight: impl Fn(&mut dyn RngCore, usize, usize) -> f64 + Send + Sync + 'static,
+    ) -> Self {
+        ClosureInitializer {
+            name,
+            weight: Arc::new(weight),
+        }
+    }
+}
+
+impl PartialEq for ClosureInitializer {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.weight, &other.weight)
+    }
+}
+
+impl Debug for ClosureInitializer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClosureInitializer")
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+/// How a layer draws its starting weights. Every initializer but `StandardUniform` starts the
+/// biases at zero.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum WeightInitializer {
+    /// Weights and biases uniform in [0, 1).
+    #[default]
+    StandardUniform,
+    XavierUniform,
+    XavierNormal,
+    HeUniform,
+    HeNormal,
+    LeCunUniform,
+    LeCunNormal,
+    /// Orthonormal rows, or columns when the layer has more neurons than inputs.
+    Orthogonal,
+    Zeros,
+    Constant(f64),
+    Closure(ClosureInitializer),
+}
+
+impl WeightInitializer {
+    /// Takes a name or the `Display` form. `Constant` needs its value, as in `constant(0.1)`.
+    pub fn from_name(name: &str) -> Option<WeightInitializer> {
+        let (name, parameters) = parse_parametric_name(name)?;
+        match (name, parameters.as_slice()) {
+            ("standard_uniform", []) => Some(WeightInitializer::StandardUniform),
+            ("xavier_uniform", []) => Some(WeightInitializer::XavierUniform),
+            ("xavier_normal", []) => Some(WeightInitializer::XavierNormal),
+            ("he_uniform", []) => Some(WeightInitializer::HeUniform),
+            ("he_normal", []) => Some(WeightInitializer::HeNormal),
+            ("lecun_uniform", []) => Some(WeightInitializer::LeCunUniform),
+            ("lecun_normal", []) => Some(WeightInitializer::LeCunNormal),
+            ("orthogonal", []) => Some(WeightInitializer::Orthogonal),
+            ("zeros", []) => Some(WeightInitializer::Zeros),
+            ("constant", [value]) => Some(WeightInitializer::Constant(*value)),
+            _ => None,
+        }
+    }
+
+    pub fn from_closure(
+        name: &'static str,
+        weight: impl Fn(&mut dyn RngCore, usize, usize) -> f64 + Send + Sync + 'static,
+    ) -> WeightInitializer {
+        WeightInitializer::Closure(ClosureInitializer::new(name, weight))
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            WeightInitializer::StandardUniform => "standard_uniform",
+            WeightInitializer::XavierUniform => "xavier_uniform",
+            WeightInitializer::XavierNormal => "xavier_normal",
+            WeightInitializer::HeUniform => "he_uniform",
+            WeightInitializer::HeNormal => "he_normal",
+            WeightInitializer::LeCunUniform => "lecun_uniform",
+            WeightInitializer::LeCunNormal => "lecun_normal",
+            WeightInitializer::Orthogonal => "orthogonal",
+            WeightInitializer::Zeros => "zeros",
+            WeightInitializer::Constant(_) => "constant",
+            WeightInitializer::Closure(initializer) => initializer.name,
+        }
+    }
+
+    /// Returns the weights of each of the `fan_out` neurons, each `fan_in` long, and their biases.
+    pub fn initialize(
+        &self,
+        rng: &mut dyn RngCore,
+        fan_in: usize,
+        fan_out: usize,
+    ) -> (Vec<Vec<f64>>, Vec<f64>) {
+        let weights = match self {
+            WeightInitializer::Orthogonal => orthogonal(rng, fan_in, fan_out),
+            _ => (0..fan_out)
+                .map(|_| {
+                    (0..fan_in)
+                        .map(|_| self.sample(rng, fan_in, fan_out))
+                        .collect()
+                })
+                .collect(),
+        };
+        let biases = match self {
+            WeightInitializer::StandardUniform => (0..fan_out).map(|_| rng.gen::<f64>()).collect(),
+            _ => vec![0.0; fan_out],
+        };
+        (weights, biases)
+    }
+
+    fn sample(&self, rng: &mut dyn RngCore, fan_in: usize, fan_out: usize) -> f64 {
+        // Empty layers still get finite scales.
+        let (fan_in_scale, fan_sum_scale) =
+            (fan_in.max(1) as f64, (fan_in + fan_out).max(1) as f64);
+        match self {
+            WeightInitializer::StandardUniform => rng.gen::<f64>(),
+            WeightInitializer::XavierUniform => sample_uniform(rng, (6.0 / fan_sum_scale).sqrt()),
+            WeightInitializer::XavierNormal => sample_normal(rng, (2.0 / fan_sum_scale).sqrt()),
+            WeightInitializer::HeUniform => sample_uniform(rng, (6.0 / fan_in_scale).sqrt()),
+            WeightInitializer::HeNormal => sample_normal(rng, (2.0 / fan_in_scale).sqrt()),
+            WeightInitializer::LeCunUniform => sample_uniform(rng, (3.0 / fan_in_scale).sqrt()),
+            WeightInitializer::LeCunNormal => sample_normal(rng, (1.0 / fan_in_scale).sqrt()),
+            WeightInitializer::Orthogonal => sample_normal(rng, 1.0),
+            WeightInitializer::Zeros => 0.0,
+            WeightInitializer::Constant(value) => *value,
+            WeightInitializer::Closure(initializer) => (initializer.weight)(rng, fan_in, fan_out),
+        }
+    }
+}
+
+impl Display for WeightInitializer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WeightInitializer::Constant(value) => write!(f, "{}({})", self.name(), value),
+            _ => write!(f, "{}", self.name()),
+        }
+    }
+}
+
+/// Uniform in [-limit, limit).
+pub fn sample_uniform(rng: &mut dyn RngCore, limit: f64) -> f64 {
+    limit * (2.0 * rng.gen::<f64>() - 1.0)
+}
+
+/// Normal with mean zero, drawn with the Box-Muller transform.
+pub fn sample_normal(rng: &mut dyn RngCore, standard_deviation: f64) -> f64 {
+    // 1 - u lies in (0, 1], so the logarithm stays finite.
+    let radius = (-2.0 * (1.0 - rng.gen::<f64>()).ln()).sqrt();
+    let angle = 2.0 * PI * rng.gen::<f64>();
+    standard_deviation * radius * angle.cos()
+}
+
+/// Orthonormalizes Gaussian vectors with modified Gram-Schmidt. The scalar dot product keeps
+/// seeded weights the same on every cpu.
+fn orthogonal(rng: &mut dyn RngCore, fan_in: usize, fan_out: usize) -> Vec<Vec<f64>> {
+    let (count, length) = (fan_in.min(fan_out), fan_in.max(fan_out));
+    let mut vectors: Vec<Vec<f64>> = Vec::with_capacity(count);
+    while vectors.len() < count {
+        let mut vector = (0..length)
+            .map(|_| sample_normal(rng, 1.0))
+            .collect::<Vec<f64>>();
+        for basis in vectors.iter() {
+            let projection = dot_scalar(&vector, basis);
+            for (value, basis_value) in vector.iter_mut().zip(basis) {
+                *value -= projection * basis_value;
+            }
+        }
+        let norm = dot_scalar(&vector, &vector).sqrt();
+        // A draw that is almost dependent on the basis so far is drawn again.
+        if norm > 1e-8 {
+            vectors.push(vector.into_iter().map(|value| value / norm).collect());
+        }
+    }
+
+    match fan_out <= fan_in {
+        true => vectors,
+        false => (0..fan_out)
+            .map(|row| vectors.iter().map(|column| column[row]).collect())
+            .collect(),
+    }
+}
@@ -1,12 +1,36 @@
 use core::f64;
-use std::{fmt::Display, vec};
+use rand::RngCore;
+use std::fmt::Display;
 
-use crate::functions::activation_functions::ActivationFunctionType;
+use crate::autodiff::{Tape, Var};
+use crate::functions::activation_functions::{Activation, ActivationFunction};
+use crate::functions::weight_constraints::WeightConstraint;
+use crate::functions::weight_initializers::WeightInitializer;
 use crate::network_model::NetworkError;
 use crate::neuron::Neuron;
+use crate::vector_math::{dot_from, scaled_add};
 
+/// A dense layer. The weights live in one row-major matrix, with a row of `input_width` weights
+/// per neuron, and everything else a neuron holds is kept in one vector per quantity.
 pub struct Layer {
-    neuron_list: Vec<Neuron>,
+    weights: Vec<f64>,
+    biases: Vec<f64>,
+    input_width: usize,
+    activation_parameters: Option<Vec<f64>>,
+    current_errors: Vec<f64>,
+    current_parameter_errors: Vec<f64>,
+    last_pre_activations: Vec<f64>,
+    last_outputs: Vec<f64>,
+    weight_gradients: Vec<f64>,
+    bias_gradients: Vec<f64>,
+    parameter_gradients: Vec<f64>,
+    accumulated_samples: usize,
+    trainable: bool,
+    neuron_trainable: Vec<bool>,
+    weight_constraints: Vec<WeightConstraint>,
+    connection_mask: Option<Vec<bool>>,
+    learning_rate: f64,
+    activation_function: ActivationFunction,
 }
 
 impl Layer {
@@ -14,132 +38,443 @@ impl Layer {
         layer_width: usize,
         input_width: usize,
         learning_rate: f64,
-        activation_function: ActivationFunctionType,
-        activation_function_prime: ActivationFunctionType,
+        activation_function: ActivationFunction,
+        weight_initializer: &WeightInitializer,
+        rng: &mut dyn RngCore,
     ) -> Layer {
-        let mut neuron_list = Vec::new();
+        let (weights_by_neuron, biases) =
+            weight_initializer.initialize(rng, input_width, layer_width);
+        let weights = weights_by_neuron.into_iter().flatten().collect();
+        let activation_parameters = activation_function
+            .initial_parameter()
+            .map(|parameter| vec![parameter; layer_width]);
 
-        for _ in 0..layer_width {
-            neuron_list.push(Neuron::new(
-                input_width,
-                learning_rate,
-                activation_function,
-                activation_function_prime,
-            ));
+        Layer {
+            weights,
+            biases,
+            input_width,
+            activation_parameters,
+            current_errors: vec![0.0; layer_width],
+            current_parameter_errors: vec![0.0; layer_width],
+            last_pre_activations: vec![0.0; layer_width],
+            last_outputs: vec![0.0; layer_width],
+            weight_gradients: vec![0.0; layer_width * input_width],
+            bias_gradients: vec![0.0; layer_width],
+            parameter_gradients: vec![0.0; layer_width],
+            accumulated_samples: 0,
+            trainable: true,
+            neuron_trainable: vec![true; layer_width],
+            weight_constraints: Vec::new(),
+            connection_mask: None,
+            learning_rate,
+            activation_function,
         }
+    }
 
-        Layer { neuron_list }
+    pub fn width(&self) -> usize {
+        self.biases.len()
     }
 
-    fn fetch_next_layer_weights_for_neuron(
-        next_layer_weights_by_neuron: &Vec<Vec<f64>>,
-        index: usize,
-    ) -> Result<Vec<f64>, NetworkError> {
-        let mut next_layer_weights_for_neuron = vec![];
-        for neuron_weights_list in next_layer_weights_by_neuron {
-            next_layer_weights_for_neuron.push(
-                *neuron_weights_list
-                    .get(index)
-                    .ok_or(NetworkError::ErrorsIncomplete)?,
-            );
+    pub fn input_width(&self) -> usize {
+        self.input_width
+    }
+
+    pub(crate) fn row(&self, index: usize) -> &[f64] {
+        &self.weights[index * self.input_width..(index + 1) * self.input_width]
+    }
+
+    /// Weights times inputs plus biases.
+    fn matrix_vector(&self, inputs: &[f64]) -> Vec<f64> {
+        (0..self.width())
+            .map(|i| dot_from(self.biases[i], self.row(i), inputs))
+            .collect()
+    }
+
+    fn activate(&self, index: usize, value: f64) -> f64 {
+        match &self.activation_parameters {
+            Some(parameters) => self
+                .activation_function
+                .compute_with_parameter(value, parameters[index]),
+            None => self.activation_function.compute(value),
         }
-        Ok(next_layer_weights_for_neuron)
+    }
+
+    fn activate_all(&self, pre_activations: &[f64]) -> Vec<f64> {
+        pre_activations
+            .iter()
+            .enumerate()
+            .map(|(i, value)| self.activate(i, *value))
+            .collect()
     }
 
     pub fn compute_m_to_n(&self, inputs: &[f64]) -> Vec<f64> {
-        let mut result_vec = Vec::new();
+        self.activate_all(&self.matrix_vector(inputs))
+    }
+
+    /// Computes the outputs like `compute_m_to_n`, keeping each neuron's pre-activation value and
+    /// output for inspection.
+    pub fn forward(&mut self, inputs: &[f64]) -> Vec<f64> {
+        self.last_pre_activations = self.matrix_vector(inputs);
+        self.last_outputs = self.activate_all(&self.last_pre_activations);
+        self.last_outputs.clone()
+    }
 
-        for neuron in self.neuron_list.iter() {
-            result_vec.push(neuron.compute(inputs));
+    fn record_activation<'t>(
+        &self,
+        tape: &'t Tape,
+        index: usize,
+        pre_activation: Var<'t>,
+    ) -> (Var<'t>, Option<Var<'t>>) {
+        match &self.activation_parameters {
+            Some(parameters) => {
+                let parameter = tape.var(parameters[index]);
+                (
+                    self.activation_function
+                        .record_with_parameter(pre_activation, parameter),
+                    Some(parameter),
+                )
+            }
+            None => (self.activation_function.record(pre_activation), None),
         }
+    }
 
-        result_vec
+    /// Records the layer outputs onto `tape`, treating the parameters as constants.
+    pub fn record<'t>(&self, tape: &'t Tape, inputs: &[Var<'t>]) -> Vec<Var<'t>> {
+        (0..self.width())
+            .map(|i| {
+                let pre_activation = self
+                    .row(i)
+                    .iter()
+                    .zip(inputs.iter())
+                    .fold(tape.var(self.biases[i]), |acc, (weight, input)| {
+                        acc + *input * *weight
+                    });
+                self.record_activation(tape, i, pre_activation).0
+            })
+            .collect()
     }
 
     pub fn compute_n_to_1(&self, inputs: &[f64]) -> f64 {
-        let mut result_vec = Vec::new();
+        self.compute_m_to_n(inputs)
+            .into_iter()
+            .fold(0_f64, |acc, item| acc + item)
+    }
+
+    pub fn compute_m_to_n_without_activation(&self, inputs: &[f64]) -> Vec<f64> {
+        self.matrix_vector(inputs)
+    }
 
-        for neuron in self.neuron_list.iter() {
-            result_vec.push(neuron.compute(inputs));
+    pub fn compute_n_to_1_without_activation_layer(&self, inputs: &[f64]) -> f64 {
+        self.matrix_vector(inputs)
+            .into_iter()
+            .fold(0_f64, |acc, item| acc + item)
+    }
+
+    /// The current errors carried back to the inputs: the transposed weights times the errors.
+    pub fn input_errors(&self) -> Vec<f64> {
+        let mut input_errors = vec![0.0; self.input_width];
+        for (i, error) in self.current_errors.iter().enumerate() {
+            scaled_add(&mut input_errors, *error, self.row(i));
         }
+        input_errors
+    }
 
-        result_vec.into_iter().fold(0_f64, |acc, item| acc + item)
+    pub fn compute_absolute_error(&self, input_params: &[f64], output: f64) -> f64 {
+        self.compute_n_to_1(input_params) - output
     }
 
-    pub fn compute_n_to_1_without_activation_layer(&self, inputs: &[f64]) -> f64 {
-        let mut result_vec = Vec::new();
+    pub fn accumulate_bias(&self) -> f64 {
+        self.biases.iter().sum()
+    }
 
-        for neuron in self.neuron_list.iter() {
-            result_vec.push(neuron.compute_without_activation(inputs));
+    pub fn step_gradient(&mut self, inputs: &[f64]) {
+        if !self.trainable {
+            return;
         }
+        self.accumulate_gradient(inputs);
+        self.apply_gradients();
+    }
 
-        result_vec.into_iter().fold(0_f64, |acc, item| acc + item)
+    pub fn accumulate_gradient(&mut self, inputs: &[f64]) {
+        let input_width = self.input_width;
+        for (i, error) in self.current_errors.iter().enumerate() {
+            let gradients = &mut self.weight_gradients[i * input_width..(i + 1) * input_width];
+            match &self.connection_mask {
+                Some(mask) => {
+                    let mask = &mask[i * input_width..(i + 1) * input_width];
+                    for ((gradient, input), connected) in gradients.iter_mut().zip(inputs).zip(mask)
+                    {
+                        if *connected {
+                            *gradient += error * input;
+                        }
+                    }
+                }
+                None => scaled_add(gradients, *error, inputs),
+            }
+            self.bias_gradients[i] += error;
+            self.parameter_gradients[i] += self.current_parameter_errors[i];
+        }
+        self.accumulated_samples += 1;
     }
 
-    pub fn compute_layer_errors(
+    /// Steps the parameters of every trainable neuron against the mean of the accumulated
+    /// gradients, then clears them.
+    pub fn apply_gradients(&mut self) {
+        if !self.trainable || self.accumulated_samples == 0 {
+            self.zero_gradients();
+            return;
+        }
+        let step = self.learning_rate / self.accumulated_samples as f64;
+        let input_width = self.input_width;
+
+        for i in 0..self.width() {
+            if !self.neuron_trainable[i] {
+                continue;
+            }
+            if let Some(parameters) = self.activation_parameters.as_mut() {
+                parameters[i] -= step * self.parameter_gradients[i];
+            }
+            let row = i * input_width..(i + 1) * input_width;
+            for (weight, gradient) in self.weights[row.clone()]
+                .iter_mut()
+                .zip(&self.weight_gradients[row])
+            {
+                *weight -= step * gradient;
+            }
+            self.biases[i] -= step * self.bias_gradients[i];
+        }
+        self.apply_weight_constraints();
+
+        self.zero_gradients();
+    }
+
+    pub fn zero_gradients(&mut self) {
+        self.weight_gradients
+            .iter_mut()
+            .for_each(|value| *value = 0.0);
+        self.bias_gradients
+            .iter_mut()
+            .for_each(|value| *value = 0.0);
+        self.parameter_gradients
+            .iter_mut()
+            .for_each(|value| *value = 0.0);
+        self.accumulated_samples = 0;
+    }
+
+    /// Accumulated gradients, summed over samples, laid out like the weights.
+    pub fn weight_gradients(&self) -> &[f64] {
+        &self.weight_gradients
+    }
+
+    pub fn bias_gradients(&self) -> &[f64] {
+        &self.bias_gradients
+    }
+
+    pub fn accumulated_samples(&self) -> usize {
+        self.accumulated_samples
+    }
+
+    pub fn set_final_layer_error(&mut self, error: f64) -> Result<(), NetworkError> {
+        if self.current_errors.is_empty() {
+            return Err(NetworkError::EmptyNeuronList);
+        }
+        self.current_errors[0] = error;
+        self.current_parameter_errors[0] = 0.0;
+        Ok(())
+    }
+
+    /// Sets the errors directly on the pre-activation values, so the activation parameters took
+    /// no part in producing them.
+    pub fn set_final_layer_errors(&mut self, errors: &[f64]) -> Result<(), NetworkError> {
+        if errors.len() != self.width() {
+            return Err(NetworkError::ErrorsIncomplete);
+        }
+        self.current_errors.copy_from_slice(errors);
+        self.current_parameter_errors
+            .iter_mut()
+            .for_each(|error| *error = 0.0);
+        Ok(())
+    }
+
+    /// Takes the error of each neuron with respect to its activated output and returns the
+    /// errors with respect to the pre-activation values kept by the last `forward`.
+    pub fn set_final_layer_output_errors(
         &mut self,
-        inputs: &[f64],
-        next_layer_errors_caused: &[f64],
-        next_layer_weights_by_neuron: &Vec<Vec<f64>>,
+        output_errors: &[f64],
     ) -> Result<Vec<f64>, NetworkError> {
-        self.neuron_list
-            .iter_mut()
+        if output_errors.len() != self.width() {
+            return Err(NetworkError::ErrorsIncomplete);
+        }
+        let tape = Tape::new();
+        let recorded = self
+            .last_pre_activations
+            .iter()
             .enumerate()
-            .map(|(i, neuron)| {
-                let next_layer_weights_for_this_neuron =
-                    Layer::fetch_next_layer_weights_for_neuron(next_layer_weights_by_neuron, i)?;
-                neuron.calculate_error(
-                    inputs,
-                    next_layer_errors_caused,
-                    &next_layer_weights_for_this_neuron,
-                )
+            .map(|(i, pre_activation)| {
+                let pre_activation = tape.var(*pre_activation);
+                let (output, parameter) = self.record_activation(&tape, i, pre_activation);
+                (pre_activation, parameter, output)
             })
-            .collect()
+            .collect::<Vec<(Var, Option<Var>, Var)>>();
+
+        // Each output depends on its own neuron alone, so a single backward pass over the
+        // outputs weighted by their errors reaches every neuron with its own error.
+        let weighted_outputs = recorded
+            .iter()
+            .zip(output_errors)
+            .map(|((_, _, output), output_error)| *output * *output_error)
+            .reduce(|acc, output| acc + output);
+        if let Some(weighted_outputs) = weighted_outputs {
+            let gradients = weighted_outputs.backward();
+            for (i, (pre_activation, parameter, _)) in recorded.iter().enumerate() {
+                self.current_errors[i] = gradients.wrt(*pre_activation);
+                self.current_parameter_errors[i] = parameter
+                    .map(|parameter| gradients.wrt(parameter))
+                    .unwrap_or(0.0);
+            }
+        }
+        Ok(self.current_errors.clone())
     }
 
-    pub fn compute_absolute_error(&self, input_params: &[f64], output: f64) -> f64 {
-        self.compute_n_to_1(input_params) - output
+    /// A frozen layer keeps every parameter through training, whatever its neurons' own flags
+    /// say, while errors still flow back through it.
+    pub fn set_trainable(&mut self, trainable: bool) {
+        self.trainable = trainable;
     }
 
-    pub fn accumulate_bias(&self) -> f64 {
-        let mut acc = 0.0;
-        self.neuron_list
-            .iter()
-            .for_each(|neuron| acc += neuron.get_bias());
-        acc
+    pub fn is_trainable(&self) -> bool {
+        self.trainable
     }
 
-    pub fn step_gradient(&mut self, inputs: &[f64]) {
-        for neuron in self.neuron_list.iter_mut() {
-            neuron.step_gradient(inputs);
+    /// Constrains the incoming weights of every neuron, now and after each of its updates.
+    pub fn set_weight_constraints(
+        &mut self,
+        weight_constraints: &[WeightConstraint],
+    ) -> Result<(), NetworkError> {
+        for constraint in weight_constraints {
+            constraint.validate()?;
         }
+        self.weight_constraints = weight_constraints.to_vec();
+        self.apply_weight_constraints();
+        Ok(())
     }
 
-    pub fn set_final_layer_error(&mut self, error: f64) -> Result<(), NetworkError> {
-        let neuron = self
-            .neuron_list
-            .first_mut()
-            .ok_or(NetworkError::EmptyNeuronList)?;
-        neuron.set_error(error);
+    fn apply_weight_constraints(&mut self) {
+        if self.input_width > 0 {
+            for row in self.weights.chunks_exact_mut(self.input_width) {
+                for constraint in self.weight_constraints.iter() {
+                    constraint.apply(row);
+                }
+            }
+        }
+        if let Some(mask) = &self.connection_mask {
+            for (weight, connected) in self.weights.iter_mut().zip(mask) {
+                if !connected {
+                    *weight = 0.0;
+                }
+            }
+        }
+    }
+
+    /// Takes one row per neuron, with one entry per incoming weight, and fixes the weights of
+    /// every false entry at zero.
+    pub fn set_connection_mask(
+        &mut self,
+        connection_mask: &[Vec<bool>],
+    ) -> Result<(), NetworkError> {
+        if connection_mask.len() != self.width()
+            || connection_mask
+                .iter()
+                .any(|row| row.len() != self.input_width)
+        {
+            return Err(NetworkError::IncorrectConnectionMask);
+        }
+        self.connection_mask = Some(connection_mask.concat());
+        self.apply_weight_constraints();
         Ok(())
     }
 
-    pub fn get_weights_by_neurons(&self) -> Vec<Vec<f64>> {
-        let mut weights_by_neurons = vec![];
+    /// Lets masked weights train again, starting from zero.
+    pub fn clear_connection_mask(&mut self) {
+        self.connection_mask = None;
+    }
+
+    pub fn set_neuron_trainable(
+        &mut self,
+        index: usize,
+        trainable: bool,
+    ) -> Result<(), NetworkError> {
+        let layer_width = self.width();
+        *self
+            .neuron_trainable
+            .get_mut(index)
+            .ok_or(NetworkError::NeuronIndexOutOfRange(index, layer_width))? = trainable;
+        Ok(())
+    }
+
+    pub fn get_activation_function(&self) -> ActivationFunction {
+        self.activation_function.clone()
+    }
 
-        for neuron in self.neuron_list.iter() {
-            weights_by_neurons.push(neuron.weights.clone());
+    pub fn neurons(&self) -> Vec<Neuron<'_>> {
+        (0..self.width())
+            .map(|index| Neuron::new(self, index))
+            .collect()
+    }
+
+    pub fn neuron(&self, index: usize) -> Result<Neuron<'_>, NetworkError> {
+        match index < self.width() {
+            true => Ok(Neuron::new(self, index)),
+            false => Err(NetworkError::NeuronIndexOutOfRange(index, self.width())),
         }
+    }
 
-        weights_by_neurons
+    pub fn is_neuron_trainable(&self, index: usize) -> Result<bool, NetworkError> {
+        self.neuron_trainable
+            .get(index)
+            .map(|trainable| self.trainable && *trainable)
+            .ok_or(NetworkError::NeuronIndexOutOfRange(index, self.width()))
+    }
+
+    pub fn activation_parameters(&self) -> Option<&[f64]> {
+        self.activation_parameters.as_deref()
+    }
+
+    pub fn learning_rate(&self) -> f64 {
+        self.learning_rate
+    }
+
+    /// All weights, row-major with one row per neuron.
+    pub fn weights(&self) -> &[f64] {
+        &self.weights
+    }
+
+    pub fn get_biases(&self) -> &[f64] {
+        &self.biases
+    }
+
+    pub fn current_errors(&self) -> &[f64] {
+        &self.current_errors
+    }
+
+    pub fn last_pre_activations(&self) -> &[f64] {
+        &self.last_pre_activations
+    }
+
+    pub fn last_outputs(&self) -> &[f64] {
+        &self.last_outputs
+    }
+
+    pub fn get_weights_by_neurons(&self) -> Vec<Vec<f64>> {
+        (0..self.width()).map(|i| self.row(i).to_vec()).collect()
     }
 }
 
 impl Display for Layer {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut current_string: String = "".to_owned();
-        current_string += "Layer:";
-        for neuron in self.neuron_list.iter() {
+        current_string += &format!("Layer ({}):", self.activation_function);
+        for neuron in self.neurons() {
             current_string += &format!("\n\t\t{:#}", neuron);
         }
         write!(f, "{}", current_string)
@@ -1,5 +1,7 @@
+pub mod autodiff;
 pub mod functions;
 pub mod layer;
 pub mod network_model;
 pub mod neuron;
 pub mod sequential;
+pub mod vector_math;
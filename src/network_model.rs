@@ -1,4 +1,12 @@
+use crate::functions::activation_functions::{softmax, softmax_backward, ActivationFunction};
+use crate::functions::error_functions::{
+    gaussian_negative_log_likelihood, gaussian_negative_log_likelihood_prime, pinball,
+    pinball_prime, softmax_cross_entropy, softmax_cross_entropy_prime, ErrorFunction, Loss,
+};
+use crate::functions::weight_constraints::WeightConstraint;
+use crate::functions::weight_initializers::WeightInitializer;
 use crate::layer::Layer;
+use rand::RngCore;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -17,6 +25,32 @@ pub enum NetworkError {
     IncorrectNetworkWidthList,
     #[error("Input length is incompatible with network definition.")]
     InvalidInputInserted,
+    #[error("Layer activation list must hold one activation per layer.")]
+    IncorrectLayerActivationList,
+    #[error("Layer initializer list must hold one initializer per layer.")]
+    IncorrectLayerInitializerList,
+    #[error("Output head produces {0} values, use the vector predictions instead.")]
+    NonScalarOutput(usize),
+    #[error("Target data is width {0}, incompatible with output width {1}.")]
+    TargetIncompatibleWidth(usize, usize),
+    #[error("Quantile {0} must lie strictly between 0 and 1.")]
+    InvalidQuantile(f64),
+    #[error("Quantiles must be a non-empty, strictly increasing list.")]
+    InvalidQuantileList,
+    #[error("Huber delta {0} must be finite and positive.")]
+    InvalidHuberDelta(f64),
+    #[error("Softmax heads need at least 2 classes, got {0}.")]
+    InvalidClassCount(usize),
+    #[error("Sample weights are length {0}, incompatible with {1} inputs.")]
+    SampleWeightsIncompatibleLength(usize, usize),
+    #[error("Layer index {0} is out of range for a network of depth {1}.")]
+    LayerIndexOutOfRange(usize, usize),
+    #[error("Neuron index {0} is out of range for a layer of width {1}.")]
+    NeuronIndexOutOfRange(usize, usize),
+    #[error("Weight constraint {0:?} has invalid bounds.")]
+    InvalidWeightConstraint(WeightConstraint),
+    #[error("Connection mask must hold one entry per weight of every neuron in the layer.")]
+    IncorrectConnectionMask,
 }
 pub enum NetworkType {
     MultiLayerPerceptron,
@@ -24,37 +58,238 @@ pub enum NetworkType {
     SingleNeuron,
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub enum OutputHead {
+    /// A single output neuron, passed through the output layer activation and trained with the
+    /// network error function.
+    Scalar,
+    /// Softmax probabilities over the given number of classes, trained with cross-entropy
+    /// through the fused softmax + cross-entropy gradient.
+    Softmax(usize),
+    /// Softmax probabilities over the given number of classes, trained with the network error
+    /// function on each probability and backpropagated through the full softmax Jacobian.
+    /// Categorical cross-entropy takes the fused gradient instead, as it does on `Softmax`.
+    SoftmaxWithErrorFunction(usize),
+    /// One output neuron per quantile, passed through the output layer activation and each
+    /// trained with the pinball loss for its quantile in place of the network error function.
+    /// A single aim is shared by every quantile, listed in increasing order.
+    Quantiles(Vec<f64>),
+    /// A raw mean and log-variance, trained with the Gaussian negative log-likelihood in place
+    /// of the network error function. Predictions hold the mean and the variance.
+    Gaussian,
+}
+
+impl OutputHead {
+    pub fn output_width(&self) -> usize {
+        match self {
+            OutputHead::Scalar => 1,
+            OutputHead::Softmax(classes) | OutputHead::SoftmaxWithErrorFunction(classes) => {
+                *classes
+            }
+            OutputHead::Quantiles(quantiles) => quantiles.len(),
+            OutputHead::Gaussian => 2,
+        }
+    }
+
+    /// Number of aims each sample is trained against.
+    pub fn target_width(&self) -> usize {
+        match self {
+            OutputHead::Quantiles(_) | OutputHead::Gaussian => 1,
+            _ => self.output_width(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), NetworkError> {
+        match self {
+            OutputHead::Quantiles(quantiles) => {
+                if quantiles.is_empty() || quantiles.windows(2).any(|pair| pair[0] >= pair[1]) {
+                    return Err(NetworkError::InvalidQuantileList);
+                }
+                match quantiles
+                    .iter()
+                    .find(|quantile| !(**quantile > 0.0 && **quantile < 1.0))
+                {
+                    Some(quantile) => Err(NetworkError::InvalidQuantile(*quantile)),
+                    None => Ok(()),
+                }
+            }
+            OutputHead::Softmax(classes) | OutputHead::SoftmaxWithErrorFunction(classes)
+                if *classes < 2 =>
+            {
+                Err(NetworkError::InvalidClassCount(*classes))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Softmax and Gaussian heads replace the output layer activation, so they read the raw
+    /// output layer values instead.
+    pub fn applies_output_activation(&self) -> bool {
+        match self {
+            OutputHead::Scalar | OutputHead::Quantiles(_) => true,
+            OutputHead::Softmax(_)
+            | OutputHead::SoftmaxWithErrorFunction(_)
+            | OutputHead::Gaussian => false,
+        }
+    }
+
+    pub fn compute(&self, outputs: &[f64]) -> Vec<f64> {
+        match self {
+            OutputHead::Scalar | OutputHead::Quantiles(_) => outputs.to_vec(),
+            OutputHead::Softmax(_) | OutputHead::SoftmaxWithErrorFunction(_) => softmax(outputs),
+            OutputHead::Gaussian => match outputs {
+                [mean, log_variance] => vec![*mean, log_variance.exp()],
+                _ => outputs.to_vec(),
+            },
+        }
+    }
+
+    /// Returns the sample loss and the error of each output neuron, taken with respect to the
+    /// value the output layer produced: the activated output when the head applies the output
+    /// activation, the logit otherwise.
+    pub fn compute_loss_and_output_errors(
+        &self,
+        aims: &[f64],
+        outputs: &[f64],
+        error_function: &ErrorFunction,
+    ) -> Result<(f64, Vec<f64>), NetworkError> {
+        if aims.len() != self.target_width() {
+            return Err(NetworkError::TargetIncompatibleWidth(
+                aims.len(),
+                self.target_width(),
+            ));
+        }
+
+        let loss_and_errors = match self {
+            OutputHead::Scalar => (
+                compute_total_loss(aims, outputs, error_function),
+                aims.iter()
+                    .zip(outputs)
+                    .map(|(aim, output)| error_function.gradient(*aim, *output))
+                    .collect(),
+            ),
+            OutputHead::Softmax(_) => (
+                softmax_cross_entropy(aims, outputs),
+                softmax_cross_entropy_prime(aims, outputs),
+            ),
+            OutputHead::SoftmaxWithErrorFunction(_)
+                if *error_function == ErrorFunction::CategoricalCrossEntropy =>
+            {
+                (
+                    softmax_cross_entropy(aims, outputs),
+                    softmax_cross_entropy_prime(aims, outputs),
+                )
+            }
+            OutputHead::Quantiles(quantiles) => (
+                quantiles
+                    .iter()
+                    .zip(outputs)
+                    .map(|(quantile, output)| pinball(aims[0], *output, *quantile))
+                    .sum(),
+                quantiles
+                    .iter()
+                    .zip(outputs)
+                    .map(|(quantile, output)| pinball_prime(aims[0], *output, *quantile))
+                    .collect(),
+            ),
+            OutputHead::Gaussian => {
+                let (mean, log_variance) = match outputs {
+                    [mean, log_variance] => (*mean, *log_variance),
+                    _ => return Err(NetworkError::ErrorsIncomplete),
+                };
+                let (mean_error, log_variance_error) =
+                    gaussian_negative_log_likelihood_prime(aims[0], mean, log_variance);
+                (
+                    gaussian_negative_log_likelihood(aims[0], mean, log_variance),
+                    vec![mean_error, log_variance_error],
+                )
+            }
+            OutputHead::SoftmaxWithErrorFunction(_) => {
+                let probabilities = softmax(outputs);
+                let probability_errors = aims
+                    .iter()
+                    .zip(probabilities.iter())
+                    .map(|(aim, probability)| error_function.gradient(*aim, *probability))
+                    .collect::<Vec<f64>>();
+                (
+                    compute_total_loss(aims, &probabilities, error_function),
+                    softmax_backward(&probabilities, &probability_errors),
+                )
+            }
+        };
+
+        Ok(loss_and_errors)
+    }
+}
+
+fn compute_total_loss(aims: &[f64], outputs: &[f64], error_function: &ErrorFunction) -> f64 {
+    aims.iter()
+        .zip(outputs)
+        .map(|(aim, output)| error_function.value(*aim, *output))
+        .sum()
+}
+
+/// Losses measured while training, one per sample in the order they were seen.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TrainingReport {
+    pub sample_losses: Vec<f64>,
+}
+
+impl TrainingReport {
+    pub fn mean_loss(&self) -> f64 {
+        match self.sample_losses.is_empty() {
+            true => 0.0,
+            false => self.sample_losses.iter().sum::<f64>() / self.sample_losses.len() as f64,
+        }
+    }
+}
+
 pub fn generate_layers_for_single_neuron_model(
     input_width: usize,
+    output_width: usize,
     learning_rate: f64,
-    activation_function: fn(f64) -> f64,
-    activation_function_prime: fn(f64) -> f64,
-) -> (Layer, Layer, Vec<Layer>) {
+    layer_activations: &[ActivationFunction],
+    layer_initializers: &[WeightInitializer],
+    rng: &mut dyn RngCore,
+) -> Result<(Layer, Layer, Vec<Layer>), NetworkError> {
+    let output_activation = layer_activations
+        .last()
+        .cloned()
+        .ok_or(NetworkError::IncorrectLayerActivationList)?;
+
+    let output_initializer = layer_initializers
+        .last()
+        .ok_or(NetworkError::IncorrectLayerInitializerList)?;
+
     let output_layer = Layer::new(
-        1,
+        output_width,
         input_width,
         learning_rate,
-        activation_function,
-        activation_function_prime,
+        output_activation.clone(),
+        output_initializer,
+        rng,
     );
 
     let empty_layer = Layer::new(
         0,
         input_width,
         learning_rate,
-        activation_function,
-        activation_function_prime,
+        output_activation,
+        output_initializer,
+        rng,
     );
 
-    (empty_layer, output_layer, Vec::new())
+    Ok((empty_layer, output_layer, Vec::new()))
 }
 
 pub fn generate_layers_for_two_layer_perceptron(
     network_width: &[usize],
     input_width: usize,
+    output_width: usize,
     learning_rate: f64,
-    activation_function: fn(f64) -> f64,
-    activation_function_prime: fn(f64) -> f64,
+    layer_activations: &[ActivationFunction],
+    layer_initializers: &[WeightInitializer],
+    rng: &mut dyn RngCore,
 ) -> Result<(Layer, Layer, Vec<Layer>), NetworkError> {
     let input_layer = Layer::new(
         *network_width
@@ -62,30 +297,45 @@ pub fn generate_layers_for_two_layer_perceptron(
             .ok_or(NetworkError::IncorrectNetworkWidthList)?,
         input_width,
         learning_rate,
-        activation_function,
-        activation_function_prime,
+        layer_activations
+            .first()
+            .cloned()
+            .ok_or(NetworkError::IncorrectLayerActivationList)?,
+        layer_initializers
+            .first()
+            .ok_or(NetworkError::IncorrectLayerInitializerList)?,
+        rng,
     );
 
     let output_layer = Layer::new(
-        1,
+        output_width,
         *network_width
             .first()
             .ok_or(NetworkError::IncorrectNetworkWidthList)?,
         learning_rate,
-        activation_function,
-        activation_function_prime,
+        layer_activations
+            .last()
+            .cloned()
+            .ok_or(NetworkError::IncorrectLayerActivationList)?,
+        layer_initializers
+            .last()
+            .ok_or(NetworkError::IncorrectLayerInitializerList)?,
+        rng,
     );
 
     Ok((input_layer, output_layer, Vec::new()))
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn generate_layers_for_mlp(
     network_width: &[usize],
     input_width: usize,
+    output_width: usize,
     learning_rate: f64,
-    activation_function: fn(f64) -> f64,
-    activation_function_prime: fn(f64) -> f64,
+    layer_activations: &[ActivationFunction],
+    layer_initializers: &[WeightInitializer],
     network_depth: usize,
+    rng: &mut dyn RngCore,
 ) -> Result<(Layer, Layer, Vec<Layer>), NetworkError> {
     let mut common_layers = Vec::new();
     let input_layer = Layer::new(
@@ -94,8 +344,14 @@ pub fn generate_layers_for_mlp(
             .ok_or(NetworkError::IncorrectNetworkWidthList)?,
         input_width,
         learning_rate,
-        activation_function,
-        activation_function_prime,
+        layer_activations
+            .first()
+            .cloned()
+            .ok_or(NetworkError::IncorrectLayerActivationList)?,
+        layer_initializers
+            .first()
+            .ok_or(NetworkError::IncorrectLayerInitializerList)?,
+        rng,
     );
 
     for index in 0..network_depth - 2 {
@@ -107,24 +363,103 @@ pub fn generate_layers_for_mlp(
                 .get(index)
                 .ok_or(NetworkError::IncorrectNetworkWidthList)?,
             learning_rate,
-            activation_function,
-            activation_function_prime,
+            layer_activations
+                .get(index + 1)
+                .cloned()
+                .ok_or(NetworkError::IncorrectLayerActivationList)?,
+            layer_initializers
+                .get(index + 1)
+                .ok_or(NetworkError::IncorrectLayerInitializerList)?,
+            rng,
         ))
     }
 
     let output_layer = Layer::new(
-        1,
+        output_width,
         *network_width
             .last()
             .ok_or(NetworkError::IncorrectNetworkWidthList)?,
         learning_rate,
-        activation_function,
-        activation_function_prime,
+        layer_activations
+            .last()
+            .cloned()
+            .ok_or(NetworkError::IncorrectLayerActivationList)?,
+        layer_initializers
+            .last()
+            .ok_or(NetworkError::IncorrectLayerInitializerList)?,
+        rng,
     );
 
     Ok((input_layer, output_layer, common_layers))
 }
 
+/// Gathers every optional constructor argument, so any combination of them can be built, and
+/// seeded, through `from_builder`.
+#[derive(Clone, Debug)]
+pub struct NetworkBuilder {
+    pub network_depth: usize,
+    pub network_width: Vec<usize>,
+    pub input_width: usize,
+    pub learning_rate: f64,
+    pub layer_activations: Vec<ActivationFunction>,
+    pub layer_initializers: Vec<WeightInitializer>,
+    pub error_function: ErrorFunction,
+    pub output_head: OutputHead,
+    pub seed: Option<u64>,
+}
+
+impl NetworkBuilder {
+    /// Starts from the same network as the plain `new` constructor.
+    pub fn new(
+        network_depth: usize,
+        network_width: &[usize],
+        input_width: usize,
+        learning_rate: f64,
+        activation_function: ActivationFunction,
+        error_function: ErrorFunction,
+    ) -> Self {
+        NetworkBuilder {
+            network_depth,
+            network_width: network_width.to_vec(),
+            input_width,
+            learning_rate,
+            layer_activations: vec![activation_function; network_depth],
+            layer_initializers: vec![WeightInitializer::default(); network_depth],
+            error_function,
+            output_head: OutputHead::Scalar,
+            seed: None,
+        }
+    }
+
+    pub fn output_head(mut self, output_head: OutputHead) -> Self {
+        self.output_head = output_head;
+        self
+    }
+
+    pub fn output_activation(mut self, output_activation: ActivationFunction) -> Self {
+        if let Some(last_activation) = self.layer_activations.last_mut() {
+            *last_activation = output_activation;
+        }
+        self
+    }
+
+    pub fn layer_activations(mut self, layer_activations: &[ActivationFunction]) -> Self {
+        self.layer_activations = layer_activations.to_vec();
+        self
+    }
+
+    pub fn layer_initializers(mut self, layer_initializers: &[WeightInitializer]) -> Self {
+        self.layer_initializers = layer_initializers.to_vec();
+        self
+    }
+
+    /// Without a seed the rng is seeded from entropy.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+}
+
 #[macro_export]
 macro_rules! new_network_function {
     ($network_type:ident) => {
@@ -133,10 +468,75 @@ macro_rules! new_network_function {
             network_width: &[usize],
             input_width: usize,
             learning_rate: f64,
-            activation_function: $crate::functions::activation_functions::ActivationFunctionType,
-            activation_function_prime: $crate::functions::activation_functions::ActivationFunctionType,
-            error_function: $crate::functions::error_functions::ErrorFunctionType,
+            activation_function: $crate::functions::activation_functions::ActivationFunction,
+            error_function: $crate::functions::error_functions::ErrorFunction,
+        ) -> Result<$network_type, NetworkError> {
+            $network_type::from_builder($crate::network_model::NetworkBuilder::new(
+                network_depth,
+                network_width,
+                input_width,
+                learning_rate,
+                activation_function,
+                error_function,
+            ))
+        }
+
+        /// Same as `new`, with every weight drawn from an rng seeded with `seed`.
+        pub fn new_with_seed(
+            network_depth: usize,
+            network_width: &[usize],
+            input_width: usize,
+            learning_rate: f64,
+            activation_function: $crate::functions::activation_functions::ActivationFunction,
+            error_function: $crate::functions::error_functions::ErrorFunction,
+            seed: u64,
+        ) -> Result<$network_type, NetworkError> {
+            $network_type::from_builder(
+                $crate::network_model::NetworkBuilder::new(
+                    network_depth,
+                    network_width,
+                    input_width,
+                    learning_rate,
+                    activation_function,
+                    error_function,
+                )
+                .seed(seed),
+            )
+        }
+
+        /// Takes every other option, such as output heads, per-layer activations and
+        /// initializers. The rng that draws the weights stays on the network for anything else
+        /// that needs randomness, so a seeded builder makes whole runs reproducible.
+        pub fn from_builder(
+            builder: $crate::network_model::NetworkBuilder,
         ) -> Result<$network_type, NetworkError> {
+            let $crate::network_model::NetworkBuilder {
+                network_depth,
+                network_width,
+                input_width,
+                learning_rate,
+                layer_activations,
+                layer_initializers,
+                error_function,
+                output_head,
+                seed,
+            } = builder;
+            let mut rng = match seed {
+                Some(seed) => <rand::rngs::StdRng as rand::SeedableRng>::seed_from_u64(seed),
+                None => <rand::rngs::StdRng as rand::SeedableRng>::from_entropy(),
+            };
+
+            if layer_activations.len() != network_depth {
+                return Err(NetworkError::IncorrectLayerActivationList);
+            }
+            if layer_initializers.len() != network_depth {
+                return Err(NetworkError::IncorrectLayerInitializerList);
+            }
+
+            output_head.validate()?;
+            error_function.validate()?;
+            let output_width = output_head.output_width();
+
             let network_type = match network_depth {
                 i if i == 1 => NetworkType::SingleNeuron,
                 i if i == 2 => NetworkType::TwoLayerPerceptron,
@@ -144,33 +544,43 @@ macro_rules! new_network_function {
             };
 
             let (input_layer, output_layer, common_layers) = match network_type {
-                NetworkType::MultiLayerPerceptron => $crate::network_model::generate_layers_for_mlp(
-                    network_width,
-                    input_width,
-                    learning_rate,
-                    activation_function,
-                    activation_function_prime,
-                    network_depth,
-                )?,
-                NetworkType::TwoLayerPerceptron => $crate::network_model::generate_layers_for_two_layer_perceptron(
-                    network_width,
-                    input_width,
-                    learning_rate,
-                    activation_function,
-                    activation_function_prime,
-                )?,
-                NetworkType::SingleNeuron => $crate::network_model::generate_layers_for_single_neuron_model(
-                    input_width,
-                    learning_rate,
-                    activation_function,
-                    activation_function_prime,
-                ),
+                NetworkType::MultiLayerPerceptron => {
+                    $crate::network_model::generate_layers_for_mlp(
+                        &network_width,
+                        input_width,
+                        output_width,
+                        learning_rate,
+                        &layer_activations,
+                        &layer_initializers,
+                        network_depth,
+                        &mut rng,
+                    )?
+                }
+                NetworkType::TwoLayerPerceptron => {
+                    $crate::network_model::generate_layers_for_two_layer_perceptron(
+                        &network_width,
+                        input_width,
+                        output_width,
+                        learning_rate,
+                        &layer_activations,
+                        &layer_initializers,
+                        &mut rng,
+                    )?
+                }
+                NetworkType::SingleNeuron => {
+                    $crate::network_model::generate_layers_for_single_neuron_model(
+                        input_width,
+                        output_width,
+                        learning_rate,
+                        &layer_activations,
+                        &layer_initializers,
+                        &mut rng,
+                    )?
+                }
             };
 
             let intermidiate_values: Vec<Vec<f64>> = Vec::new();
 
-            let network_width = network_width.to_vec();
-
             let network = $network_type {
                 intermediate_values: intermidiate_values,
                 network_depth,
@@ -182,6 +592,8 @@ macro_rules! new_network_function {
                 output_layer,
                 input_layer,
                 error_function,
+                output_head,
+                rng,
             };
             Ok(network)
         }
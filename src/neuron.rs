@@ -1,106 +1,67 @@
-use rand::Rng;
 use std::fmt::Display;
 
-use crate::{functions::activation_functions::ActivationFunctionType, network_model::NetworkError};
+use crate::layer::Layer;
 
-pub struct Neuron {
-    pub weights: Vec<f64>,
-    pub bias: f64,
-    current_error: f64,
-    learning_rate: f64,
-    activation_function: ActivationFunctionType,
-    activation_function_prime: ActivationFunctionType,
+/// Read-only view of one neuron of a layer. Layers hold every parameter and training buffer.
+#[derive(Clone, Copy)]
+pub struct Neuron<'a> {
+    layer: &'a Layer,
+    index: usize,
 }
 
-impl Neuron {
-    pub fn new(
-        number_of_weights: usize,
-        learning_rate: f64,
-        activation_function: ActivationFunctionType,
-        activation_function_prime: ActivationFunctionType,
-    ) -> Self {
-        let mut rng = rand::thread_rng();
-
-        let mut weights = Vec::new();
-
-        for _ in 0..number_of_weights {
-            let new_weight = rng.gen::<f64>();
-            weights.push(new_weight);
-        }
-
-        let bias = rng.gen::<f64>();
-        let current_error = 0.0;
+impl<'a> Neuron<'a> {
+    pub(crate) fn new(layer: &'a Layer, index: usize) -> Self {
+        Neuron { layer, index }
+    }
 
-        Neuron {
-            weights,
-            bias,
-            current_error,
-            learning_rate,
-            activation_function,
-            activation_function_prime,
-        }
+    pub fn weights(&self) -> &'a [f64] {
+        self.layer.row(self.index)
     }
 
-    fn multiply_and_accumulate(&self, inputs: &[f64]) -> f64 {
-        self.weights
-            .iter()
-            .zip(inputs.iter())
-            .fold(self.bias, |acc, (weight, input)| acc + (weight * input))
+    pub fn bias(&self) -> f64 {
+        self.layer.get_biases()[self.index]
     }
 
-    pub fn get_bias(&self) -> f64 {
-        self.bias
+    pub fn activation_parameter(&self) -> Option<f64> {
+        self.layer
+            .activation_parameters()
+            .map(|parameters| parameters[self.index])
     }
 
-    pub fn compute(&self, inputs: &[f64]) -> f64 {
-        (self.activation_function)(self.multiply_and_accumulate(inputs))
+    /// The error on the pre-activation value from the last backpropagation.
+    pub fn current_error(&self) -> f64 {
+        self.layer.current_errors()[self.index]
     }
 
-    pub fn compute_without_activation(&self, inputs: &[f64]) -> f64 {
-        self.multiply_and_accumulate(inputs)
+    pub fn last_pre_activation(&self) -> f64 {
+        self.layer.last_pre_activations()[self.index]
     }
 
-    pub fn calculate_error(
-        &mut self,
-        inputs: &[f64],
-        next_layer_errors_caused: &[f64],
-        next_layer_weights: &Vec<f64>,
-    ) -> Result<f64, NetworkError> {
-        let temp_factor = (self.activation_function_prime)(self.multiply_and_accumulate(inputs));
-        let weights_times_errors_caused = next_layer_errors_caused
-            .iter()
-            .zip(next_layer_weights)
-            .fold(0.0, |acc, (a, b)| acc + a * b);
-        self.current_error = weights_times_errors_caused * temp_factor;
-        Ok(self.current_error)
+    pub fn last_output(&self) -> f64 {
+        self.layer.last_outputs()[self.index]
     }
 
-    pub fn step_gradient(&mut self, inputs: &[f64]) {
-        self.weights = self
-            .weights
-            .iter()
-            .zip(inputs.iter())
-            .map(|(weight, input)| -> f64 {
-                weight.to_owned() - self.learning_rate * self.current_error * input
-            })
-            .collect::<Vec<f64>>();
-        self.bias -= self.learning_rate * self.current_error;
+    pub fn learning_rate(&self) -> f64 {
+        self.layer.learning_rate()
     }
 
-    pub fn set_error(&mut self, error: f64) {
-        self.current_error = error
+    pub fn is_trainable(&self) -> bool {
+        self.layer.is_neuron_trainable(self.index).unwrap_or(false)
     }
 }
 
-impl Display for Neuron {
+impl Display for Neuron<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut current_string: String = "".to_owned();
         current_string += "->";
-        for (i, weight) in self.weights.iter().enumerate() {
+        for (i, weight) in self.weights().iter().enumerate() {
             current_string = current_string + &format!(" Weight {}: {:.2}.", i, weight);
         }
-        current_string = current_string + &format!("\tNeuron Bias :{:.2}.", self.bias);
-        current_string = current_string + &format!("\tNeuron Error :{:.2}.", self.current_error);
+        current_string = current_string + &format!("\tNeuron Bias :{:.2}.", self.bias());
+        if let Some(parameter) = self.activation_parameter() {
+            current_string = current_string + &format!("\tActivation Parameter :{:.2}.", parameter);
+        }
+        current_string = current_string + &format!("\tNeuron Error :{:.2}.", self.current_error());
         write!(f, "{}", current_string)
     }
 }
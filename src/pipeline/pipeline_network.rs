@@ -1,5 +1,5 @@
 use crate::functions::activation_functions::ActivationFunction;
-use crate::functions::error_functions::ErrorFunction;
+use crate::functions::error_functions::{ErrorFunction, Loss};
 use crate::layer::Layer;
 use crate::network_model::{NetworkError, NetworkType};
 use std::fmt::Display;
@@ -136,53 +136,37 @@ impl PipelineNetwork {
 
         if let NetworkType::MultiLayerPerceptron = self.network_type {
             for i in (self.network_depth - 2)..0 {
-                let next_layer_weights_by_neuron = self
+                let next_layer_input_errors = self
                     .common_layers
                     .get(i + 1)
                     .unwrap_or(&self.output_layer)
-                    .get_weights_by_neurons();
+                    .input_errors();
 
                 let inputs = intermediate_values
                     .get(i)
                     .ok_or(NetworkError::IntermediateValuesIncomplete)?;
 
-                let next_layer_errors_caused = intermediate_errors
-                    .last()
-                    .ok_or(NetworkError::ErrorsIncomplete)?;
-
                 let value = self
                     .common_layers
                     .get_mut(i)
                     .ok_or(NetworkError::InvalidCommonLayers)?
-                    .compute_layer_errors(
-                        inputs,
-                        next_layer_errors_caused,
-                        &next_layer_weights_by_neuron,
-                    )?;
+                    .set_final_layer_output_errors(&next_layer_input_errors)?;
 
                 intermediate_errors.push(value)
             }
         }
 
-        let next_layer_weights_by_neuron = self
+        let next_layer_input_errors = self
             .common_layers
             .get(0)
             .unwrap_or(&self.output_layer)
-            .get_weights_by_neurons();
+            .input_errors();
 
         let inputs = intermediate_values
             .first()
             .ok_or(NetworkError::IntermediateValuesIncomplete)?;
 
-        let next_layer_errors_caused = intermediate_errors
-            .last()
-            .ok_or(NetworkError::ErrorsIncomplete)?;
-
-        self.input_layer.compute_layer_errors(
-            inputs,
-            next_layer_errors_caused,
-            &next_layer_weights_by_neuron,
-        )?;
+        self.input_layer.set_final_layer_output_errors(&next_layer_input_errors)?;
 
         Ok(())
     }
@@ -196,55 +180,39 @@ impl PipelineNetwork {
 
         if let NetworkType::MultiLayerPerceptron = self.network_type {
             for i in (self.network_depth - 2)..0 {
-                let next_layer_weights_by_neuron = self
+                let next_layer_input_errors = self
                     .common_layers
                     .get(i + 1)
                     .unwrap_or(&self.output_layer)
-                    .get_weights_by_neurons();
+                    .input_errors();
 
                 let inputs = self
                     .intermediate_values
                     .get(i)
                     .ok_or(NetworkError::IntermediateValuesIncomplete)?;
 
-                let next_layer_errors_caused = intermediate_errors
-                    .last()
-                    .ok_or(NetworkError::ErrorsIncomplete)?;
-
                 let value = self
                     .common_layers
                     .get_mut(i)
                     .ok_or(NetworkError::InvalidCommonLayers)?
-                    .compute_layer_errors(
-                        inputs,
-                        next_layer_errors_caused,
-                        &next_layer_weights_by_neuron,
-                    )?;
+                    .set_final_layer_output_errors(&next_layer_input_errors)?;
 
                 intermediate_errors.push(value)
             }
         }
 
-        let next_layer_weights_by_neuron = self
+        let next_layer_input_errors = self
             .common_layers
             .get(0)
             .unwrap_or(&self.output_layer)
-            .get_weights_by_neurons();
+            .input_errors();
 
         let inputs = self
             .intermediate_values
             .first()
             .ok_or(NetworkError::IntermediateValuesIncomplete)?;
 
-        let next_layer_errors_caused = intermediate_errors
-            .last()
-            .ok_or(NetworkError::ErrorsIncomplete)?;
-
-        self.input_layer.compute_layer_errors(
-            inputs,
-            next_layer_errors_caused,
-            &next_layer_weights_by_neuron,
-        )?;
+        self.input_layer.set_final_layer_output_errors(&next_layer_input_errors)?;
 
         Ok(())
     }
@@ -310,7 +278,7 @@ impl PipelineNetwork {
 
     fn train_iteration(&mut self, inputs: &[f64], aim: f64) -> Result<(), NetworkError> {
         let final_answer = self.feedforward_compute_iteration_no_activation(inputs)?;
-        let last_neuron_error = (self.error_function)(aim, final_answer);
+        let last_neuron_error = self.error_function.gradient(aim, final_answer);
         //println!("Inputs: {:?}", inputs);
         //println!("Resposta: {final_answer}. Objetivo:{aim}");
         //println!("Network error: {:.2?}", last_neuron_error);
@@ -354,7 +322,7 @@ impl PipelineNetwork {
         for (i, input) in inputs.iter().enumerate() {
             let (current_intermediate_values, final_answer) =
                 self.feedforward_compute_batch(input)?;
-            let last_neuron_error = (self.error_function)(
+            let last_neuron_error = self.error_function.gradient(
                 *targets.get(i).ok_or(NetworkError::InvalidInputInserted)?,
                 final_answer,
             );
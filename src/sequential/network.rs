@@ -1,7 +1,9 @@
-use crate::functions::error_functions::ErrorFunctionType;
+use crate::functions::error_functions::ErrorFunction;
+use crate::functions::weight_constraints::WeightConstraint;
 use crate::layer::Layer;
-use crate::network_model::{NetworkError, NetworkType};
+use crate::network_model::{NetworkError, NetworkType, OutputHead, TrainingReport};
 use crate::{network_display, new_network_function};
+use rand::rngs::StdRng;
 use std::fmt::Display;
 
 pub struct SequentialNetwork {
@@ -14,7 +16,9 @@ pub struct SequentialNetwork {
     common_layers: Vec<Layer>,
     output_layer: Layer,
     input_layer: Layer,
-    error_function: ErrorFunctionType,
+    error_function: ErrorFunction,
+    output_head: OutputHead,
+    rng: StdRng,
 }
 
 impl SequentialNetwork {
@@ -35,10 +39,28 @@ impl SequentialNetwork {
         Ok(output)
     }
 
+    fn feedforward_compute_iteration_outputs(
+        &mut self,
+        inputs: &[f64],
+    ) -> Result<Vec<f64>, NetworkError> {
+        self.base_feedforward_compute(inputs)?;
+
+        let outputs = compute_output_layer(
+            &mut self.output_layer,
+            &self.output_head,
+            self.intermediate_values
+                .last()
+                .ok_or(NetworkError::IntermediateValuesIncomplete)?,
+        );
+
+        Ok(outputs)
+    }
+
     fn base_feedforward_compute(&mut self, inputs: &[f64]) -> Result<(), NetworkError> {
         if inputs.len() != self.input_width {
             return Err(NetworkError::InvalidInputInserted);
         }
+        self.reset_intermediate_values();
         self.intermediate_values.push(inputs.to_vec());
         match self.network_type {
             NetworkType::SingleNeuron => (),
@@ -48,7 +70,7 @@ impl SequentialNetwork {
                     .first()
                     .ok_or(NetworkError::IntermediateValuesIncomplete)?;
 
-                let first_layer_result = self.input_layer.compute_m_to_n(inputs);
+                let first_layer_result = self.input_layer.forward(inputs);
 
                 self.intermediate_values.push(first_layer_result);
             }
@@ -63,9 +85,9 @@ impl SequentialNetwork {
 
                 let value = self
                     .common_layers
-                    .get(i)
+                    .get_mut(i)
                     .ok_or(NetworkError::InvalidCommonLayers)?
-                    .compute_m_to_n(inputs);
+                    .forward(inputs);
 
                 self.intermediate_values.push(value)
             }
@@ -77,7 +99,7 @@ impl SequentialNetwork {
     fn feedforward_compute_batch(
         &mut self,
         inputs: &[f64],
-    ) -> Result<(Vec<Vec<f64>>, f64), NetworkError> {
+    ) -> Result<(Vec<Vec<f64>>, Vec<f64>), NetworkError> {
         let mut intermediate_values = Vec::new();
         if inputs.len() != self.input_width {
             return Err(NetworkError::InvalidInputInserted);
@@ -92,7 +114,7 @@ impl SequentialNetwork {
                     .first()
                     .ok_or(NetworkError::IntermediateValuesIncomplete)?;
 
-                let first_layer_result = self.input_layer.compute_m_to_n(inputs);
+                let first_layer_result = self.input_layer.forward(inputs);
 
                 intermediate_values.push(first_layer_result);
             }
@@ -106,145 +128,62 @@ impl SequentialNetwork {
 
                 let value = self
                     .common_layers
-                    .get(i)
+                    .get_mut(i)
                     .ok_or(NetworkError::InvalidCommonLayers)?
-                    .compute_m_to_n(inputs);
+                    .forward(inputs);
 
                 intermediate_values.push(value)
             }
         }
 
-        let output = self.output_layer.compute_n_to_1_without_activation_layer(
+        let outputs = compute_output_layer(
+            &mut self.output_layer,
+            &self.output_head,
             intermediate_values
                 .last()
                 .ok_or(NetworkError::IntermediateValuesIncomplete)?,
         );
 
-        Ok((intermediate_values, output))
+        Ok((intermediate_values, outputs))
     }
 
-    fn backpropagate_error_batch(
-        &mut self,
-        final_error: f64,
-        intermediate_values: &[Vec<f64>],
-    ) -> Result<(), NetworkError> {
-        let mut intermediate_errors: Vec<Vec<f64>> = Vec::new();
-
-        self.output_layer.set_final_layer_error(final_error)?;
-
-        intermediate_errors.push(vec![final_error]);
+    /// Backpropagates through the values each layer kept from the last forward pass.
+    fn backpropagate_error(&mut self, output_errors: &[f64]) -> Result<(), NetworkError> {
+        match self.output_head.applies_output_activation() {
+            true => {
+                self.output_layer
+                    .set_final_layer_output_errors(output_errors)?;
+            }
+            false => self.output_layer.set_final_layer_errors(output_errors)?,
+        };
 
         if let NetworkType::MultiLayerPerceptron = self.network_type {
-            for i in (self.network_depth - 2)..0 {
-                let next_layer_weights_by_neuron = self
+            for i in (0..self.network_depth - 2).rev() {
+                let next_layer_input_errors = self
                     .common_layers
                     .get(i + 1)
                     .unwrap_or(&self.output_layer)
-                    .get_weights_by_neurons();
+                    .input_errors();
 
-                let inputs = intermediate_values
-                    .get(i)
-                    .ok_or(NetworkError::IntermediateValuesIncomplete)?;
-
-                let next_layer_errors_caused = intermediate_errors
-                    .last()
-                    .ok_or(NetworkError::ErrorsIncomplete)?;
-
-                let value = self
-                    .common_layers
+                self.common_layers
                     .get_mut(i)
                     .ok_or(NetworkError::InvalidCommonLayers)?
-                    .compute_layer_errors(
-                        inputs,
-                        next_layer_errors_caused,
-                        &next_layer_weights_by_neuron,
-                    )?;
-
-                intermediate_errors.push(value)
+                    .set_final_layer_output_errors(&next_layer_input_errors)?;
             }
         }
 
-        let next_layer_weights_by_neuron = self
-            .common_layers
-            .get(0)
-            .unwrap_or(&self.output_layer)
-            .get_weights_by_neurons();
-
-        let inputs = intermediate_values
-            .first()
-            .ok_or(NetworkError::IntermediateValuesIncomplete)?;
-
-        let next_layer_errors_caused = intermediate_errors
-            .last()
-            .ok_or(NetworkError::ErrorsIncomplete)?;
-
-        self.input_layer.compute_layer_errors(
-            inputs,
-            next_layer_errors_caused,
-            &next_layer_weights_by_neuron,
-        )?;
-
-        Ok(())
-    }
-
-    fn backpropagate_error_iteration(&mut self, final_error: f64) -> Result<(), NetworkError> {
-        let mut intermediate_errors: Vec<Vec<f64>> = Vec::new();
-
-        self.output_layer.set_final_layer_error(final_error)?;
-
-        intermediate_errors.push(vec![final_error]);
-
-        if let NetworkType::MultiLayerPerceptron = self.network_type {
-            for i in (self.network_depth - 2)..0 {
-                let next_layer_weights_by_neuron = self
-                    .common_layers
-                    .get(i + 1)
-                    .unwrap_or(&self.output_layer)
-                    .get_weights_by_neurons();
-
-                let inputs = self
-                    .intermediate_values
-                    .get(i)
-                    .ok_or(NetworkError::IntermediateValuesIncomplete)?;
-
-                let next_layer_errors_caused = intermediate_errors
-                    .last()
-                    .ok_or(NetworkError::ErrorsIncomplete)?;
-
-                let value = self
-                    .common_layers
-                    .get_mut(i)
-                    .ok_or(NetworkError::InvalidCommonLayers)?
-                    .compute_layer_errors(
-                        inputs,
-                        next_layer_errors_caused,
-                        &next_layer_weights_by_neuron,
-                    )?;
-
-                intermediate_errors.push(value)
-            }
+        if let NetworkType::SingleNeuron = self.network_type {
+            return Ok(());
         }
 
-        let next_layer_weights_by_neuron = self
+        let next_layer_input_errors = self
             .common_layers
-            .get(0)
-            .unwrap_or(&self.output_layer)
-            .get_weights_by_neurons();
-
-        let inputs = self
-            .intermediate_values
             .first()
-            .ok_or(NetworkError::IntermediateValuesIncomplete)?;
-
-        let next_layer_errors_caused = intermediate_errors
-            .last()
-            .ok_or(NetworkError::ErrorsIncomplete)?;
+            .unwrap_or(&self.output_layer)
+            .input_errors();
 
-        self.input_layer.compute_layer_errors(
-            inputs,
-            next_layer_errors_caused,
-            &next_layer_weights_by_neuron,
-        )?;
+        self.input_layer
+            .set_final_layer_output_errors(&next_layer_input_errors)?;
 
         Ok(())
     }
@@ -276,14 +215,14 @@ impl SequentialNetwork {
         Ok(())
     }
 
-    fn step_gradient_batch(
+    fn accumulate_gradient_batch(
         &mut self,
         intermediate_values: &[Vec<f64>],
     ) -> Result<(), NetworkError> {
         let input = intermediate_values
             .first()
             .ok_or(NetworkError::IntermediateValuesIncomplete)?;
-        self.input_layer.step_gradient(input);
+        self.input_layer.accumulate_gradient(input);
 
         if let NetworkType::MultiLayerPerceptron = self.network_type {
             for i in 0..self.network_depth - 2 {
@@ -293,7 +232,7 @@ impl SequentialNetwork {
                 self.common_layers
                     .get_mut(i)
                     .ok_or(NetworkError::InvalidCommonLayers)?
-                    .step_gradient(input);
+                    .accumulate_gradient(input);
             }
         }
 
@@ -301,96 +240,292 @@ impl SequentialNetwork {
             .last()
             .ok_or(NetworkError::IntermediateValuesIncomplete)?;
 
-        self.output_layer.step_gradient(input);
-
-        self.reset_intermediate_values();
+        self.output_layer.accumulate_gradient(input);
 
         Ok(())
     }
 
-    fn train_iteration(&mut self, inputs: &[f64], aim: f64) -> Result<(), NetworkError> {
-        let final_answer = self.feedforward_compute_iteration_no_activation(inputs)?;
-        let last_neuron_error = (self.error_function)(aim, final_answer);
-        //println!("Inputs: {:?}", inputs);
-        //println!("Resposta: {final_answer}. Objetivo:{aim}");
-        //println!("Network error: {:.2?}", last_neuron_error);
-        self.backpropagate_error_iteration(last_neuron_error)?;
-        //println!("Pos backprogation: {}", self);
+    fn apply_gradients(&mut self) {
+        self.input_layer.apply_gradients();
+        for layer in self.common_layers.iter_mut() {
+            layer.apply_gradients();
+        }
+        self.output_layer.apply_gradients();
+        self.reset_intermediate_values();
+    }
+
+    fn train_iteration(
+        &mut self,
+        inputs: &[f64],
+        aims: &[f64],
+        sample_weight: f64,
+    ) -> Result<f64, NetworkError> {
+        let outputs = self.feedforward_compute_iteration_outputs(inputs)?;
+        let (loss, last_layer_errors) = self.output_head.compute_loss_and_output_errors(
+            aims,
+            &outputs,
+            &self.error_function,
+        )?;
+        let (loss, last_layer_errors) = weight_sample(loss, last_layer_errors, sample_weight);
+        self.backpropagate_error(&last_layer_errors)?;
         self.step_gradient_iteration(inputs)?;
-        //println!("Pos gradiente: {}", self);
-        Ok(())
+        Ok(loss)
     }
 
+    fn scalar_output(&self, outputs: Vec<f64>) -> Result<f64, NetworkError> {
+        match outputs.as_slice() {
+            [output] => Ok(*output),
+            _ => Err(NetworkError::NonScalarOutput(outputs.len())),
+        }
+    }
+
+    pub fn predict_iteration(&mut self, inputs: &[f64]) -> Result<f64, NetworkError> {
+        let outputs = self.feedforward_compute_iteration_outputs(inputs)?;
+        self.reset_intermediate_values();
+        self.scalar_output(self.output_head.compute(&outputs))
+    }
+
+    pub fn predict_batch(&mut self, inputs: &[f64]) -> Result<f64, NetworkError> {
+        let outputs = self.predict_vector(inputs)?;
+        self.scalar_output(outputs)
+    }
+
+    /// Raw output layer sum, skipping the output activation and the output head.
     pub fn predict_iteration_no_activation(&mut self, inputs: &[f64]) -> Result<f64, NetworkError> {
         self.feedforward_compute_iteration_no_activation(inputs)
     }
 
+    /// Raw output layer sum, skipping the output activation and the output head.
     pub fn predict_batch_no_activation(&mut self, inputs: &[f64]) -> Result<f64, NetworkError> {
+        self.predict_vector_no_activation(inputs)
+            .map(|logits| logits.into_iter().fold(0_f64, |acc, item| acc + item))
+    }
+
+    pub fn predict_vector(&mut self, inputs: &[f64]) -> Result<Vec<f64>, NetworkError> {
         self.feedforward_compute_batch(inputs)
-            .map(|(_, return_value)| return_value)
+            .map(|(_, outputs)| self.output_head.compute(&outputs))
     }
 
+    /// Raw output layer logits, skipping the output activation and the output head.
+    pub fn predict_vector_no_activation(
+        &mut self,
+        inputs: &[f64],
+    ) -> Result<Vec<f64>, NetworkError> {
+        let (intermediate_values, _) = self.feedforward_compute_batch(inputs)?;
+        let logits = self.output_layer.compute_m_to_n_without_activation(
+            intermediate_values
+                .last()
+                .ok_or(NetworkError::IntermediateValuesIncomplete)?,
+        );
+        Ok(logits)
+    }
+
+    /// Runs one epoch, updating the network after every sample.
     pub fn iterations_train(
         &mut self,
         inputs: &[Vec<f64>],
         targets: &[f64],
-    ) -> Result<(), NetworkError> {
-        for (i, input) in inputs.iter().enumerate() {
-            self.train_iteration(
+    ) -> Result<TrainingReport, NetworkError> {
+        self.iterations_train_weighted(inputs, targets, &vec![1.0; inputs.len()])
+    }
+
+    /// Scales each sample loss, and the error it backpropagates, by its weight.
+    pub fn iterations_train_weighted(
+        &mut self,
+        inputs: &[Vec<f64>],
+        targets: &[f64],
+        sample_weights: &[f64],
+    ) -> Result<TrainingReport, NetworkError> {
+        let targets = wrap_scalar_targets(targets);
+        self.iterations_train_vector_weighted(inputs, &targets, sample_weights)
+    }
+
+    pub fn iterations_train_vector(
+        &mut self,
+        inputs: &[Vec<f64>],
+        targets: &[Vec<f64>],
+    ) -> Result<TrainingReport, NetworkError> {
+        self.iterations_train_vector_weighted(inputs, targets, &vec![1.0; inputs.len()])
+    }
+
+    pub fn iterations_train_vector_weighted(
+        &mut self,
+        inputs: &[Vec<f64>],
+        targets: &[Vec<f64>],
+        sample_weights: &[f64],
+    ) -> Result<TrainingReport, NetworkError> {
+        check_sample_weights(inputs, sample_weights)?;
+        let mut report = TrainingReport::default();
+        for (i, (input, sample_weight)) in inputs.iter().zip(sample_weights).enumerate() {
+            report.sample_losses.push(self.train_iteration(
                 input,
-                *targets.get(i).ok_or(NetworkError::InvalidInputInserted)?,
-            )?;
+                targets.get(i).ok_or(NetworkError::InvalidInputInserted)?,
+                *sample_weight,
+            )?);
         }
-        Ok(())
+        Ok(report)
     }
 
+    /// Runs one batch, backpropagating every sample and then updating the network once with
+    /// the mean of their gradients.
     pub fn batch_train(
         &mut self,
-        inputs: &Vec<Vec<f64>>,
+        inputs: &[Vec<f64>],
         targets: &[f64],
-    ) -> Result<(), NetworkError> {
-        let mut total_error = 0.0;
-        let mut total_intermediate_values = Vec::new();
-        for (i, input) in inputs.iter().enumerate() {
-            let (current_intermediate_values, final_answer) =
-                self.feedforward_compute_batch(input)?;
-            let last_neuron_error = (self.error_function)(
-                *targets.get(i).ok_or(NetworkError::InvalidInputInserted)?,
-                final_answer,
-            );
-            total_error += last_neuron_error;
-
-            if total_intermediate_values.is_empty() {
-                total_intermediate_values = current_intermediate_values;
-            } else {
-                for (index, list) in current_intermediate_values.iter().enumerate() {
-                    for (secondary_index, item) in list.iter().enumerate() {
-                        total_intermediate_values[index][secondary_index] += item;
-                    }
-                }
-            }
+    ) -> Result<TrainingReport, NetworkError> {
+        self.batch_train_weighted(inputs, targets, &vec![1.0; inputs.len()])
+    }
+
+    /// Scales each sample loss, and the error it adds to the batch average, by its weight.
+    pub fn batch_train_weighted(
+        &mut self,
+        inputs: &[Vec<f64>],
+        targets: &[f64],
+        sample_weights: &[f64],
+    ) -> Result<TrainingReport, NetworkError> {
+        let targets = wrap_scalar_targets(targets);
+        self.batch_train_vector_weighted(inputs, &targets, sample_weights)
+    }
+
+    pub fn batch_train_vector(
+        &mut self,
+        inputs: &[Vec<f64>],
+        targets: &[Vec<f64>],
+    ) -> Result<TrainingReport, NetworkError> {
+        self.batch_train_vector_weighted(inputs, targets, &vec![1.0; inputs.len()])
+    }
+
+    pub fn batch_train_vector_weighted(
+        &mut self,
+        inputs: &[Vec<f64>],
+        targets: &[Vec<f64>],
+        sample_weights: &[f64],
+    ) -> Result<TrainingReport, NetworkError> {
+        check_sample_weights(inputs, sample_weights)?;
+        let mut report = TrainingReport::default();
+        for (i, (input, sample_weight)) in inputs.iter().zip(sample_weights).enumerate() {
+            let (intermediate_values, outputs) = self.feedforward_compute_batch(input)?;
+            let (loss, last_layer_errors) = self.output_head.compute_loss_and_output_errors(
+                targets.get(i).ok_or(NetworkError::InvalidInputInserted)?,
+                &outputs,
+                &self.error_function,
+            )?;
+            let (loss, last_layer_errors) = weight_sample(loss, last_layer_errors, *sample_weight);
+            report.sample_losses.push(loss);
+
+            self.backpropagate_error(&last_layer_errors)?;
+            self.accumulate_gradient_batch(&intermediate_values)?;
         }
-        println!("Inputs: {:?}", inputs);
-        let average_intermediate_values = total_intermediate_values
-            .iter()
-            .map(|item| {
-                item.iter()
-                    .map(|value| value / inputs.len() as f64)
-                    .collect()
-            })
-            .collect::<Vec<Vec<f64>>>();
-        let average_error = total_error / inputs.len() as f64;
-        println!("Network error: {:.2?}", average_error);
-        self.backpropagate_error_batch(average_error, &average_intermediate_values)?;
-        println!("Pos backprogation: {}", self);
-        self.step_gradient_batch(&average_intermediate_values)?;
-        println!("Pos gradiente: {}", self);
+        self.apply_gradients();
+        Ok(report)
+    }
+
+    /// Layers are indexed from the input layer, at 0, to the output layer, at depth - 1.
+    pub fn layer(&self, index: usize) -> Result<&Layer, NetworkError> {
+        let out_of_range = NetworkError::LayerIndexOutOfRange(index, self.network_depth);
+        match self.network_type {
+            NetworkType::SingleNeuron if index == 0 => Ok(&self.output_layer),
+            NetworkType::SingleNeuron => Err(out_of_range),
+            _ if index == 0 => Ok(&self.input_layer),
+            _ if index == self.network_depth - 1 => Ok(&self.output_layer),
+            _ => self.common_layers.get(index - 1).ok_or(out_of_range),
+        }
+    }
+
+    /// Every layer, from the input layer to the output layer. Neurons keep the values of the
+    /// last sample that went through them.
+    pub fn layers(&self) -> Vec<&Layer> {
+        match self.network_type {
+            NetworkType::SingleNeuron => vec![&self.output_layer],
+            _ => std::iter::once(&self.input_layer)
+                .chain(self.common_layers.iter())
+                .chain(std::iter::once(&self.output_layer))
+                .collect(),
+        }
+    }
+
+    pub fn layer_mut(&mut self, index: usize) -> Result<&mut Layer, NetworkError> {
+        let out_of_range = NetworkError::LayerIndexOutOfRange(index, self.network_depth);
+        match self.network_type {
+            NetworkType::SingleNeuron if index == 0 => Ok(&mut self.output_layer),
+            NetworkType::SingleNeuron => Err(out_of_range),
+            _ if index == 0 => Ok(&mut self.input_layer),
+            _ if index == self.network_depth - 1 => Ok(&mut self.output_layer),
+            _ => self.common_layers.get_mut(index - 1).ok_or(out_of_range),
+        }
+    }
+
+    pub fn set_layer_trainable(
+        &mut self,
+        index: usize,
+        trainable: bool,
+    ) -> Result<(), NetworkError> {
+        self.layer_mut(index)?.set_trainable(trainable);
         Ok(())
     }
 
+    pub fn set_layer_weight_constraints(
+        &mut self,
+        index: usize,
+        weight_constraints: &[WeightConstraint],
+    ) -> Result<(), NetworkError> {
+        self.layer_mut(index)?
+            .set_weight_constraints(weight_constraints)
+    }
+
+    pub fn set_layer_connection_mask(
+        &mut self,
+        index: usize,
+        connection_mask: &[Vec<bool>],
+    ) -> Result<(), NetworkError> {
+        self.layer_mut(index)?.set_connection_mask(connection_mask)
+    }
+
+    /// The rng the weights were drawn from. Shuffling samples with it keeps seeded runs
+    /// reproducible.
+    pub fn rng(&mut self) -> &mut StdRng {
+        &mut self.rng
+    }
+
     pub fn reset_intermediate_values(&mut self) {
         self.intermediate_values = vec![];
     }
 }
 
+fn compute_output_layer(
+    output_layer: &mut Layer,
+    output_head: &OutputHead,
+    inputs: &[f64],
+) -> Vec<f64> {
+    let outputs = output_layer.forward(inputs);
+    match output_head.applies_output_activation() {
+        true => outputs,
+        false => output_layer.last_pre_activations().to_vec(),
+    }
+}
+
+fn wrap_scalar_targets(targets: &[f64]) -> Vec<Vec<f64>> {
+    targets.iter().map(|target| vec![*target]).collect()
+}
+
+fn check_sample_weights(inputs: &[Vec<f64>], sample_weights: &[f64]) -> Result<(), NetworkError> {
+    match sample_weights.len() == inputs.len() {
+        true => Ok(()),
+        false => Err(NetworkError::SampleWeightsIncompatibleLength(
+            sample_weights.len(),
+            inputs.len(),
+        )),
+    }
+}
+
+fn weight_sample(loss: f64, output_errors: Vec<f64>, sample_weight: f64) -> (f64, Vec<f64>) {
+    (
+        sample_weight * loss,
+        output_errors
+            .into_iter()
+            .map(|error| sample_weight * error)
+            .collect(),
+    )
+}
+
 network_display!(SequentialNetwork);
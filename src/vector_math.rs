@@ -0,0 +1,92 @@
+//! Inner loops of the dense forward and backward passes. With the `simd` feature they run on
+//! AVX when the cpu supports it, falling back to the scalar loops otherwise.
+
+/// The vectorized dot product adds its terms in another order than the scalar one, so the two
+/// differ by at most `DOT_PRODUCT_TOLERANCE * n * sum(|a_i * b_i|)` over `n` terms. Scaled
+/// additions are exact.
+pub const DOT_PRODUCT_TOLERANCE: f64 = 2.0 * f64::EPSILON;
+
+pub fn dot(first: &[f64], second: &[f64]) -> f64 {
+    dot_from(0.0, first, second)
+}
+
+/// Adds the dot product to `initial`. The scalar loop folds every term into `initial` in turn.
+pub fn dot_from(initial: f64, first: &[f64], second: &[f64]) -> f64 {
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    if is_x86_feature_detected!("avx") {
+        // SAFETY: the cpu supports avx.
+        return initial + unsafe { avx::dot(first, second) };
+    }
+    fold_products(initial, first, second)
+}
+
+pub fn dot_scalar(first: &[f64], second: &[f64]) -> f64 {
+    fold_products(0.0, first, second)
+}
+
+fn fold_products(initial: f64, first: &[f64], second: &[f64]) -> f64 {
+    first
+        .iter()
+        .zip(second)
+        .fold(initial, |acc, (a, b)| acc + a * b)
+}
+
+/// Adds `scale` times each value to the matching target.
+pub fn scaled_add(target: &mut [f64], scale: f64, values: &[f64]) {
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    if is_x86_feature_detected!("avx") {
+        // SAFETY: the cpu supports avx.
+        return unsafe { avx::scaled_add(target, scale, values) };
+    }
+    scaled_add_scalar(target, scale, values)
+}
+
+pub fn scaled_add_scalar(target: &mut [f64], scale: f64, values: &[f64]) {
+    for (target, value) in target.iter_mut().zip(values) {
+        *target += scale * value;
+    }
+}
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+mod avx {
+    use std::arch::x86_64::*;
+
+    const LANES: usize = 4;
+
+    #[target_feature(enable = "avx")]
+    pub unsafe fn dot(first: &[f64], second: &[f64]) -> f64 {
+        let length = first.len().min(second.len());
+        let (first, second) = (&first[..length], &second[..length]);
+        let mut sums = _mm256_setzero_pd();
+        for (a, b) in first.chunks_exact(LANES).zip(second.chunks_exact(LANES)) {
+            let product = _mm256_mul_pd(_mm256_loadu_pd(a.as_ptr()), _mm256_loadu_pd(b.as_ptr()));
+            sums = _mm256_add_pd(sums, product);
+        }
+
+        let mut lanes = [0.0; LANES];
+        _mm256_storeu_pd(lanes.as_mut_ptr(), sums);
+        let tail = length - length % LANES;
+        lanes.iter().sum::<f64>() + super::dot_scalar(&first[tail..], &second[tail..])
+    }
+
+    /// Multiplies and adds without fusing, so every element matches the scalar loop.
+    #[target_feature(enable = "avx")]
+    pub unsafe fn scaled_add(target: &mut [f64], scale: f64, values: &[f64]) {
+        let length = target.len().min(values.len());
+        let (target, values) = (&mut target[..length], &values[..length]);
+        let scales = _mm256_set1_pd(scale);
+        for (t, v) in target
+            .chunks_exact_mut(LANES)
+            .zip(values.chunks_exact(LANES))
+        {
+            let product = _mm256_mul_pd(scales, _mm256_loadu_pd(v.as_ptr()));
+            _mm256_storeu_pd(
+                t.as_mut_ptr(),
+                _mm256_add_pd(_mm256_loadu_pd(t.as_ptr()), product),
+            );
+        }
+
+        let tail = length - length % LANES;
+        super::scaled_add_scalar(&mut target[tail..], scale, &values[tail..]);
+    }
+}
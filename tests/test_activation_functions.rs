@@ -0,0 +1,157 @@
+use rusty_network::functions::activation_functions::{
+    sigmoid, softmax, softmax_backward, softplus, Activation, ActivationFunction,
+};
+
+const ALL_ACTIVATIONS: [ActivationFunction; 12] = [
+    ActivationFunction::Relu,
+    ActivationFunction::Sigmoid,
+    ActivationFunction::Identity,
+    ActivationFunction::Tanh,
+    ActivationFunction::LeakyRelu(0.01),
+    ActivationFunction::Elu(1.0),
+    ActivationFunction::Selu,
+    ActivationFunction::Gelu,
+    ActivationFunction::Swish,
+    ActivationFunction::Softplus,
+    ActivationFunction::Softsign,
+    ActivationFunction::PRelu(0.25),
+];
+
+#[test]
+fn test_activation_name_round_trip() {
+    for activation in ALL_ACTIVATIONS {
+        assert_eq!(
+            ActivationFunction::from_name(activation.name()),
+            Some(activation)
+        );
+    }
+}
+
+#[test]
+fn test_parametric_activations_round_trip_through_display() {
+    for activation in [
+        ActivationFunction::LeakyRelu(0.2),
+        ActivationFunction::Elu(0.35),
+        ActivationFunction::PRelu(0.1),
+    ] {
+        assert_eq!(
+            ActivationFunction::from_name(&activation.to_string()),
+            Some(activation)
+        );
+    }
+    assert_eq!(ActivationFunction::from_name("relu(0.2)"), None);
+    assert_eq!(ActivationFunction::from_name("elu(fast)"), None);
+}
+
+#[test]
+fn test_derivatives_match_finite_differences() {
+    let step = 1e-6;
+    // Sample points stay away from 0.0, where relu-like functions have a kink.
+    let sample_points = [-6.0, -2.5, -1.0, -0.3, 0.2, 0.7, 1.5, 3.0, 6.0];
+
+    for activation in ALL_ACTIVATIONS {
+        for value in sample_points {
+            let numerical = (activation.compute(value + step) - activation.compute(value - step))
+                / (2.0 * step);
+            let analytical = activation.derivative(value);
+            assert!(
+                (numerical - analytical).abs() < 1e-6,
+                "{} derivative at {}: analytical {}, numerical {}",
+                activation,
+                value,
+                analytical,
+                numerical
+            );
+        }
+    }
+}
+
+#[test]
+fn test_extreme_inputs_stay_finite() {
+    for activation in ALL_ACTIVATIONS {
+        for value in [-1000.0, 1000.0] {
+            assert!(activation.compute(value).is_finite());
+            assert!(activation.derivative(value).is_finite());
+        }
+    }
+    assert_eq!(sigmoid(-1000.0), 0.0);
+    assert_eq!(softplus(1000.0), 1000.0);
+}
+
+#[test]
+fn test_parameter_derivatives_match_finite_differences() {
+    let step = 1e-6;
+    let parameter = 0.3;
+
+    for activation in ALL_ACTIVATIONS {
+        for value in [-2.0, -0.5, 0.5, 2.0] {
+            let numerical = (activation.compute_with_parameter(value, parameter + step)
+                - activation.compute_with_parameter(value, parameter - step))
+                / (2.0 * step);
+            let analytical = activation.parameter_derivative(value, parameter);
+            assert!((numerical - analytical).abs() < 1e-6);
+        }
+    }
+}
+
+#[test]
+fn test_softmax_is_stable_and_normalized() {
+    let probabilities = softmax(&[1000.0, 1001.0, 1002.0]);
+    let shifted = softmax(&[0.0, 1.0, 2.0]);
+
+    assert!((probabilities.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+    for (probability, expected) in probabilities.iter().zip(shifted) {
+        assert!((probability - expected).abs() < 1e-12);
+    }
+}
+
+#[test]
+fn test_softmax_backward_matches_finite_differences() {
+    let step = 1e-6;
+    let logits = [0.3, -1.2, 2.0, 0.5];
+    let output_gradient = [0.7, -0.4, 1.1, 0.2];
+    let weighted_sum = |logits: &[f64]| -> f64 {
+        softmax(logits)
+            .iter()
+            .zip(output_gradient)
+            .map(|(probability, gradient)| probability * gradient)
+            .sum()
+    };
+
+    let analytical = softmax_backward(&softmax(&logits), &output_gradient);
+    for (i, analytical_value) in analytical.iter().enumerate() {
+        let mut forward = logits;
+        let mut backward = logits;
+        forward[i] += step;
+        backward[i] -= step;
+        let numerical = (weighted_sum(&forward) - weighted_sum(&backward)) / (2.0 * step);
+        assert!((numerical - analytical_value).abs() < 1e-6);
+    }
+}
+
+#[test]
+fn test_closure_activation_captures_runtime_parameters() {
+    let slope = 0.2;
+    let closure_leaky_relu = ActivationFunction::from_closures(
+        "closure_leaky_relu",
+        move |value| ActivationFunction::LeakyRelu(slope).compute(value),
+        move |value| ActivationFunction::LeakyRelu(slope).derivative(value),
+    );
+
+    for value in [-3.0, -0.5, 0.5, 3.0] {
+        assert_eq!(
+            closure_leaky_relu.compute(value),
+            ActivationFunction::LeakyRelu(slope).compute(value)
+        );
+        assert_eq!(
+            closure_leaky_relu.derivative(value),
+            ActivationFunction::LeakyRelu(slope).derivative(value)
+        );
+    }
+    assert_eq!(format!("{}", closure_leaky_relu), "closure_leaky_relu");
+    assert_eq!(closure_leaky_relu, closure_leaky_relu.clone());
+    assert_ne!(
+        closure_leaky_relu,
+        ActivationFunction::from_closures("closure_leaky_relu", |value| value, |_| 1.0)
+    );
+}
@@ -0,0 +1,113 @@
+use rusty_network::{
+    autodiff::{derivative, Tape, Var},
+    functions::{
+        activation_functions::{Activation, ActivationFunction, AutodiffActivation},
+        error_functions::{autodiff_loss_prime, mean_absolute_error_prime, squared_loss_prime},
+        weight_initializers::WeightInitializer,
+    },
+    layer::Layer,
+};
+
+fn mish<'t>(value: Var<'t>) -> Var<'t> {
+    value * (value.exp().ln_1p()).tanh()
+}
+
+fn squared_loss<'t>(aim: Var<'t>, final_answer: Var<'t>) -> Var<'t> {
+    (aim - final_answer).powi(2)
+}
+
+#[test]
+fn test_gradients_of_composed_operations() {
+    let tape = Tape::new();
+    let x = tape.var(1.5);
+    let y = tape.var(-0.5);
+
+    let output = (x * y + x.exp()) / (2.0 - y);
+    let gradients = output.backward();
+
+    let denominator = 2.0 - y.value();
+    let expected_x = (y.value() + x.value().exp()) / denominator;
+    let expected_y = x.value() / denominator
+        + (x.value() * y.value() + x.value().exp()) / (denominator * denominator);
+    assert!((gradients.wrt(x) - expected_x).abs() < 1e-12);
+    assert!((gradients.wrt(y) - expected_y).abs() < 1e-12);
+}
+
+#[test]
+fn test_autodiff_activation_matches_finite_differences() {
+    let activation = ActivationFunction::Autodiff(AutodiffActivation::new("mish", mish));
+    let step = 1e-6;
+
+    for value in [-3.0, -0.7, 0.0, 0.4, 2.5] {
+        let numerical =
+            (activation.compute(value + step) - activation.compute(value - step)) / (2.0 * step);
+        assert!((activation.derivative(value) - numerical).abs() < 1e-6);
+        assert_eq!(derivative(mish, value).0, activation.compute(value));
+    }
+    assert_eq!(activation.name(), "mish");
+}
+
+#[test]
+fn test_autodiff_activations_compare_by_function() {
+    let activation = AutodiffActivation::new("mish", mish);
+    assert_eq!(activation, AutodiffActivation::new("mish", mish));
+    assert_ne!(
+        activation,
+        AutodiffActivation::new("mish", |value| value.tanh())
+    );
+}
+
+#[test]
+fn test_autodiff_loss_matches_hand_written_gradient() {
+    for (aim, final_answer) in [(1.0, 0.5), (-2.0, 3.0), (0.0, 0.0)] {
+        assert_eq!(
+            autodiff_loss_prime(squared_loss, aim, final_answer),
+            squared_loss_prime(aim, final_answer)
+        );
+    }
+}
+
+#[test]
+fn test_layer_record_matches_compute() {
+    let layer = Layer::new(
+        3,
+        2,
+        0.01,
+        ActivationFunction::Tanh,
+        &WeightInitializer::XavierUniform,
+        &mut rand::thread_rng(),
+    );
+    let inputs = [0.3, -0.8];
+
+    let tape = Tape::new();
+    let input_vars = inputs
+        .iter()
+        .map(|input| tape.var(*input))
+        .collect::<Vec<_>>();
+    let outputs = layer.record(&tape, &input_vars);
+
+    for (recorded, computed) in outputs.iter().zip(layer.compute_m_to_n(&inputs)) {
+        assert!((recorded.value() - computed).abs() < 1e-12);
+    }
+
+    let step = 1e-6;
+    let gradients = outputs[0].backward();
+    let mut shifted = inputs;
+    shifted[1] += step;
+    let numerical = (layer.compute_m_to_n(&shifted)[0] - outputs[0].value()) / step;
+    assert!((gradients.wrt(input_vars[1]) - numerical).abs() < 1e-5);
+}
+
+#[test]
+fn test_autodiff_absolute_loss_matches_hand_written_gradient() {
+    fn absolute_error<'t>(aim: Var<'t>, final_answer: Var<'t>) -> Var<'t> {
+        (aim - final_answer).abs()
+    }
+
+    for (aim, final_answer) in [(1.0, 0.5), (-2.0, 3.0), (0.0, 0.0), (0.7, 0.7)] {
+        assert_eq!(
+            autodiff_loss_prime(absolute_error, aim, final_answer),
+            mean_absolute_error_prime(aim, final_answer)
+        );
+    }
+}
@@ -0,0 +1,220 @@
+use rusty_network::functions::error_functions::{
+    binary_cross_entropy, binary_cross_entropy_with_logits, gaussian_negative_log_likelihood,
+    gaussian_negative_log_likelihood_prime, ErrorFunction, Loss, DEFAULT_FOCAL_ALPHA,
+    DEFAULT_FOCAL_GAMMA, DEFAULT_HUBER_DELTA, DEFAULT_PINBALL_QUANTILE,
+};
+
+const REGRESSION_SAMPLES: [(f64, f64); 5] =
+    [(1.0, 0.3), (1.0, 2.5), (-1.0, 0.4), (0.0, -1.7), (0.5, 0.9)];
+const PROBABILITY_SAMPLES: [(f64, f64); 5] =
+    [(1.0, 0.3), (0.0, 0.3), (1.0, 0.95), (0.0, 0.02), (0.4, 0.6)];
+const MARGIN_SAMPLES: [(f64, f64); 5] = [
+    (1.0, 0.3),
+    (-1.0, 0.4),
+    (1.0, 2.5),
+    (-1.0, -1.7),
+    (1.0, -0.6),
+];
+
+fn all_error_functions() -> Vec<(ErrorFunction, &'static [(f64, f64)])> {
+    vec![
+        (ErrorFunction::SquaredLoss, &REGRESSION_SAMPLES),
+        (ErrorFunction::BinaryCrossEntropy, &PROBABILITY_SAMPLES),
+        (
+            ErrorFunction::BinaryCrossEntropyWithLogits,
+            &REGRESSION_SAMPLES,
+        ),
+        (ErrorFunction::CategoricalCrossEntropy, &PROBABILITY_SAMPLES),
+        (
+            ErrorFunction::Huber(DEFAULT_HUBER_DELTA),
+            &REGRESSION_SAMPLES,
+        ),
+        (ErrorFunction::MeanAbsoluteError, &REGRESSION_SAMPLES),
+        (ErrorFunction::LogCosh, &REGRESSION_SAMPLES),
+        (ErrorFunction::Hinge, &MARGIN_SAMPLES),
+        (ErrorFunction::SquaredHinge, &MARGIN_SAMPLES),
+        (
+            ErrorFunction::Pinball(DEFAULT_PINBALL_QUANTILE),
+            &REGRESSION_SAMPLES,
+        ),
+        (
+            ErrorFunction::Focal {
+                gamma: DEFAULT_FOCAL_GAMMA,
+                alpha: DEFAULT_FOCAL_ALPHA,
+            },
+            &PROBABILITY_SAMPLES,
+        ),
+    ]
+}
+
+#[test]
+fn test_error_function_name_round_trip() {
+    for (error_function, _) in all_error_functions() {
+        assert_eq!(
+            ErrorFunction::from_name(error_function.name()),
+            Some(error_function)
+        );
+    }
+}
+
+#[test]
+fn test_parametric_error_functions_round_trip_through_display() {
+    for error_function in [
+        ErrorFunction::Huber(0.3),
+        ErrorFunction::Pinball(0.9),
+        ErrorFunction::Focal {
+            gamma: 1.5,
+            alpha: 0.6,
+        },
+    ] {
+        assert_eq!(
+            ErrorFunction::from_name(&error_function.to_string()),
+            Some(error_function)
+        );
+    }
+    assert_eq!(ErrorFunction::from_name("focal(1.5)"), None);
+}
+
+#[test]
+fn test_invalid_huber_deltas_are_rejected() {
+    for name in ["huber(-1)", "huber(0)", "huber(NaN)", "huber(inf)"] {
+        assert_eq!(ErrorFunction::from_name(name), None);
+    }
+    for delta in [-1.0, 0.0, f64::NAN, f64::INFINITY] {
+        assert!(ErrorFunction::Huber(delta).validate().is_err());
+    }
+    assert!(ErrorFunction::Huber(0.5).validate().is_ok());
+}
+
+#[test]
+fn test_gradients_match_finite_differences() {
+    let step = 1e-6;
+
+    for (error_function, samples) in all_error_functions() {
+        for (aim, final_answer) in samples.iter().cloned() {
+            let numerical = (error_function.value(aim, final_answer + step)
+                - error_function.value(aim, final_answer - step))
+                / (2.0 * step);
+            let analytical = error_function.gradient(aim, final_answer);
+            assert!(
+                (numerical - analytical).abs() < 1e-5,
+                "{} gradient at ({}, {}): analytical {}, numerical {}",
+                error_function,
+                aim,
+                final_answer,
+                analytical,
+                numerical
+            );
+        }
+    }
+}
+
+#[test]
+fn test_binary_cross_entropy_with_logits_matches_probabilities() {
+    for logit in [-30.0, -2.0, 0.0, 1.5, 30.0] {
+        let probability = 1.0 / (1.0 + f64::exp(-logit));
+        for aim in [0.0, 1.0] {
+            let from_logits = binary_cross_entropy_with_logits(aim, logit);
+            assert!(from_logits.is_finite());
+            if probability > 1e-9 && probability < 1.0 - 1e-9 {
+                assert!((from_logits - binary_cross_entropy(aim, probability)).abs() < 1e-6);
+            }
+        }
+    }
+    assert!((binary_cross_entropy_with_logits(0.0, 1000.0) - 1000.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_robust_losses_bound_outlier_gradients() {
+    let (aim, outlier) = (0.0, 1e6);
+
+    assert_eq!(ErrorFunction::Huber(2.5).gradient(aim, outlier), 2.5);
+    assert_eq!(ErrorFunction::Huber(2.5).gradient(outlier, aim), -2.5);
+    assert_eq!(ErrorFunction::MeanAbsoluteError.gradient(aim, outlier), 1.0);
+    assert_eq!(ErrorFunction::MeanAbsoluteError.gradient(aim, aim), 0.0);
+    assert!((ErrorFunction::LogCosh.gradient(aim, outlier) - 1.0).abs() < 1e-12);
+    assert!((ErrorFunction::LogCosh.value(aim, outlier) - (outlier - 2f64.ln())).abs() < 1e-6);
+
+    // Inside delta Huber is half the squared loss, whatever delta is.
+    for delta in [0.5, 1.0, 3.0] {
+        assert!((ErrorFunction::Huber(delta).value(aim, 0.1) - 0.005).abs() < 1e-12);
+    }
+}
+
+#[test]
+fn test_hinge_losses_vanish_beyond_the_margin() {
+    for error_function in [ErrorFunction::Hinge, ErrorFunction::SquaredHinge] {
+        for (aim, final_answer) in [(1.0, 1.0), (1.0, 3.0), (-1.0, -1.5)] {
+            assert_eq!(error_function.value(aim, final_answer), 0.0);
+            assert_eq!(error_function.gradient(aim, final_answer), 0.0);
+        }
+    }
+    assert_eq!(ErrorFunction::Hinge.gradient(-1.0, 0.0), 1.0);
+    assert_eq!(ErrorFunction::SquaredHinge.value(1.0, -1.0), 4.0);
+}
+
+#[test]
+fn test_gaussian_negative_log_likelihood_gradient() {
+    let step = 1e-6;
+
+    for (aim, mean, log_variance) in [(1.0, 0.3, 0.0), (-2.0, 0.5, -1.5), (0.4, 0.4, 2.0)] {
+        let (mean_error, log_variance_error) =
+            gaussian_negative_log_likelihood_prime(aim, mean, log_variance);
+        let numerical_mean = (gaussian_negative_log_likelihood(aim, mean + step, log_variance)
+            - gaussian_negative_log_likelihood(aim, mean - step, log_variance))
+            / (2.0 * step);
+        let numerical_log_variance =
+            (gaussian_negative_log_likelihood(aim, mean, log_variance + step)
+                - gaussian_negative_log_likelihood(aim, mean, log_variance - step))
+                / (2.0 * step);
+        assert!((mean_error - numerical_mean).abs() < 1e-5);
+        assert!((log_variance_error - numerical_log_variance).abs() < 1e-5);
+    }
+}
+
+#[test]
+fn test_closure_loss_captures_runtime_parameters() {
+    let delta = 0.3;
+    let closure_huber = ErrorFunction::from_closures(
+        "closure_huber",
+        move |aim, final_answer| ErrorFunction::Huber(delta).value(aim, final_answer),
+        move |aim, final_answer| ErrorFunction::Huber(delta).gradient(aim, final_answer),
+    );
+
+    for (aim, final_answer) in REGRESSION_SAMPLES {
+        assert_eq!(
+            closure_huber.value(aim, final_answer),
+            ErrorFunction::Huber(delta).value(aim, final_answer)
+        );
+        assert_eq!(
+            closure_huber.gradient(aim, final_answer),
+            ErrorFunction::Huber(delta).gradient(aim, final_answer)
+        );
+    }
+    assert_eq!(closure_huber.name(), "closure_huber");
+    assert_eq!(closure_huber, closure_huber.clone());
+    assert_eq!(ErrorFunction::from_name("closure_huber"), None);
+}
+
+#[test]
+fn test_focal_loss_reduces_to_weighted_cross_entropy() {
+    for (aim, final_answer) in PROBABILITY_SAMPLES {
+        let focal = ErrorFunction::Focal {
+            gamma: 0.0,
+            alpha: 0.5,
+        };
+        assert!(
+            (focal.value(aim, final_answer) - 0.5 * binary_cross_entropy(aim, final_answer)).abs()
+                < 1e-12
+        );
+    }
+
+    // Confident, correct answers are down-weighted far more than under cross-entropy.
+    let focal = ErrorFunction::Focal {
+        gamma: 2.0,
+        alpha: 0.5,
+    };
+    let easy_ratio = focal.value(1.0, 0.95) / binary_cross_entropy(1.0, 0.95);
+    let hard_ratio = focal.value(1.0, 0.2) / binary_cross_entropy(1.0, 0.2);
+    assert!(easy_ratio < 0.01 && hard_ratio > 0.3);
+}
@@ -1,7 +1,13 @@
 use rand::Rng;
 use rusty_network::{
-    functions::activation_functions::{relu, relu_prime},
+    autodiff::Tape,
+    functions::{
+        activation_functions::ActivationFunction, error_functions::ErrorFunction,
+        weight_initializers::WeightInitializer,
+    },
     layer::Layer,
+    network_model::NetworkError,
+    sequential::network::SequentialNetwork,
 };
 
 #[test]
@@ -11,7 +17,14 @@ fn test_zero_input_vec() {
 
     //println!("Current random integer:{}", random_integer);
 
-    let layer = Layer::new(random_integer, random_integer, 0.01, relu, relu_prime);
+    let layer = Layer::new(
+        random_integer,
+        random_integer,
+        0.01,
+        ActivationFunction::Relu,
+        &WeightInitializer::default(),
+        &mut rng,
+    );
 
     let mut input_vec = Vec::with_capacity(random_integer);
     for _ in 0..random_integer {
@@ -20,3 +33,120 @@ fn test_zero_input_vec() {
 
     assert_eq!(layer.accumulate_bias(), layer.compute_n_to_1(&input_vec));
 }
+
+#[test]
+fn test_connection_mask_shape_is_checked() {
+    let mut layer = Layer::new(
+        2,
+        3,
+        0.01,
+        ActivationFunction::Identity,
+        &WeightInitializer::Constant(1.0),
+        &mut rand::thread_rng(),
+    );
+
+    assert!(matches!(
+        layer.set_connection_mask(&[vec![true; 3]]),
+        Err(NetworkError::IncorrectConnectionMask)
+    ));
+    assert!(matches!(
+        layer.set_connection_mask(&[vec![true; 3], vec![true; 2]]),
+        Err(NetworkError::IncorrectConnectionMask)
+    ));
+
+    layer
+        .set_connection_mask(&[vec![true, false, true], vec![false, true, false]])
+        .unwrap();
+    assert_eq!(
+        layer.get_weights_by_neurons(),
+        vec![vec![1.0, 0.0, 1.0], vec![0.0, 1.0, 0.0]]
+    );
+    assert_eq!(layer.compute_m_to_n(&[1.0, 10.0, 100.0]), vec![101.0, 10.0]);
+}
+
+#[test]
+fn test_masked_connections_stay_at_zero_while_training() {
+    let inputs = vec![vec![1.0, 0.0], vec![0.0, 1.0], vec![1.0, 1.0]];
+    let targets = vec![1.0, -1.0, 0.5];
+    // Each hidden neuron only sees its own input.
+    let mask = vec![vec![true, false], vec![false, true]];
+
+    let mut new_network = SequentialNetwork::new_with_seed(
+        2,
+        &[2],
+        2,
+        0.05,
+        ActivationFunction::Identity,
+        ErrorFunction::SquaredLoss,
+        7,
+    )
+    .unwrap();
+    new_network.set_layer_connection_mask(0, &mask).unwrap();
+
+    for _ in 0..100 {
+        new_network.iterations_train(&inputs, &targets).unwrap();
+        new_network.batch_train(&inputs, &targets).unwrap();
+    }
+
+    let weights = new_network.layer_mut(0).unwrap().get_weights_by_neurons();
+    assert_eq!((weights[0][1], weights[1][0]), (0.0, 0.0));
+    assert!(weights[0][0] != 0.0 && weights[1][1] != 0.0);
+}
+
+#[test]
+fn test_matrix_layer_matches_tape() {
+    let inputs = [0.5, -1.5, 2.0];
+    let output_errors = [0.3, -0.8];
+
+    let mut layer = Layer::new(
+        2,
+        3,
+        0.1,
+        ActivationFunction::PRelu(0.25),
+        &WeightInitializer::XavierNormal,
+        &mut rand::thread_rng(),
+    );
+    assert_eq!(layer.weights(), layer.get_weights_by_neurons().concat());
+    let weights = layer.get_weights_by_neurons();
+    let biases = layer.get_biases().to_vec();
+
+    // The whole layer recorded on a tape gives the errors each input should receive.
+    let tape = Tape::new();
+    let input_vars = inputs
+        .iter()
+        .map(|input| tape.var(*input))
+        .collect::<Vec<_>>();
+    let recorded = layer.record(&tape, &input_vars);
+    let weighted_outputs = recorded
+        .iter()
+        .zip(&output_errors)
+        .map(|(output, error)| *output * *error)
+        .reduce(|acc, output| acc + output)
+        .unwrap();
+    let gradients = weighted_outputs.backward();
+
+    let outputs = layer.forward(&inputs);
+    for (output, recorded) in outputs.iter().zip(&recorded) {
+        assert_eq!(*output, recorded.value());
+    }
+    let errors = layer.set_final_layer_output_errors(&output_errors).unwrap();
+    for (input_var, found) in input_vars.iter().zip(layer.input_errors()) {
+        assert!((gradients.wrt(*input_var) - found).abs() < 1e-12);
+    }
+
+    layer.step_gradient(&inputs);
+    for (i, neuron) in layer.neurons().iter().enumerate() {
+        let pre_activation = neuron.last_pre_activation();
+        let slope = if pre_activation > 0.0 { 1.0 } else { 0.25 };
+        assert!((errors[i] - output_errors[i] * slope).abs() < 1e-12);
+
+        for ((weight, found), input) in weights[i].iter().zip(neuron.weights()).zip(&inputs) {
+            assert!((weight - 0.1 * errors[i] * input - found).abs() < 1e-12);
+        }
+        assert!((biases[i] - 0.1 * errors[i] - neuron.bias()).abs() < 1e-12);
+        let parameter_error = output_errors[i] * pre_activation.min(0.0);
+        assert!(
+            (0.25 - 0.1 * parameter_error - neuron.activation_parameter().unwrap()).abs() < 1e-12
+        );
+    }
+}
@@ -1,7 +1,7 @@
 use rand::Rng;
 use rusty_network::{
-    functions::activation_functions::{relu, relu_prime},
-    neuron::Neuron,
+    functions::{activation_functions::ActivationFunction, weight_initializers::WeightInitializer},
+    layer::Layer,
 };
 
 #[test]
@@ -9,12 +9,107 @@ fn test_zero_input_vec() {
     let mut rng = rand::thread_rng();
     let random_integer: usize = rng.gen::<usize>() % 1000_usize;
 
-    let neuron = Neuron::new(random_integer, 0.01, relu, relu_prime);
+    let layer = Layer::new(
+        1,
+        random_integer,
+        0.01,
+        ActivationFunction::Relu,
+        &WeightInitializer::StandardUniform,
+        &mut rng,
+    );
 
-    let mut input_vec = Vec::with_capacity(random_integer);
-    for _ in 0..random_integer {
-        input_vec.push(0.0)
-    }
+    let input_vec = vec![0.0; random_integer];
 
-    assert_eq!(neuron.get_bias(), neuron.compute(&input_vec));
+    assert_eq!(
+        layer.neuron(0).unwrap().bias(),
+        layer.compute_n_to_1(&input_vec)
+    );
+}
+
+#[test]
+fn test_prelu_slope_is_trained() {
+    let mut layer = Layer::new(
+        1,
+        1,
+        0.1,
+        ActivationFunction::PRelu(0.25),
+        &WeightInitializer::Constant(1.0),
+        &mut rand::thread_rng(),
+    );
+
+    let inputs = [-2.0];
+    assert_eq!(layer.forward(&inputs), vec![-0.5]);
+
+    layer.set_final_layer_output_errors(&[1.0]).unwrap();
+    layer.step_gradient(&inputs);
+
+    let slope = layer.neuron(0).unwrap().activation_parameter().unwrap();
+    assert!((slope - 0.45).abs() < 1e-12);
+}
+
+#[test]
+fn test_gradients_accumulate_until_applied() {
+    let mut layer = Layer::new(
+        1,
+        2,
+        0.1,
+        ActivationFunction::Identity,
+        &WeightInitializer::Constant(1.0),
+        &mut rand::thread_rng(),
+    );
+
+    layer.set_final_layer_errors(&[2.0]).unwrap();
+    layer.accumulate_gradient(&[1.0, 3.0]);
+    layer.set_final_layer_errors(&[-1.0]).unwrap();
+    layer.accumulate_gradient(&[2.0, 1.0]);
+
+    // Nothing moves until the buffers are applied.
+    assert_eq!(layer.weights(), &[1.0, 1.0]);
+    assert_eq!(layer.weight_gradients(), &[0.0, 5.0]);
+    assert_eq!(layer.bias_gradients(), &[1.0]);
+    assert_eq!(layer.accumulated_samples(), 2);
+
+    layer.apply_gradients();
+    let neuron = layer.neuron(0).unwrap();
+    assert!((neuron.weights()[0] - 1.0).abs() < 1e-12);
+    assert!((neuron.weights()[1] - (1.0 - 0.1 * 5.0 / 2.0)).abs() < 1e-12);
+    assert!((neuron.bias() - (-0.1 * 1.0 / 2.0)).abs() < 1e-12);
+    assert_eq!(layer.weight_gradients(), &[0.0, 0.0]);
+    assert_eq!(layer.accumulated_samples(), 0);
+
+    // Applying an empty buffer is a no-op.
+    layer.apply_gradients();
+    assert!((layer.neuron(0).unwrap().bias() + 0.05).abs() < 1e-12);
+}
+
+#[test]
+fn test_frozen_neuron_keeps_parameters_but_passes_errors() {
+    let mut layer = Layer::new(
+        2,
+        1,
+        0.1,
+        ActivationFunction::Tanh,
+        &WeightInitializer::Constant(0.5),
+        &mut rand::thread_rng(),
+    );
+    layer.set_neuron_trainable(0, false).unwrap();
+    assert!(layer.set_neuron_trainable(2, false).is_err());
+    assert!(!layer.neuron(0).unwrap().is_trainable());
+    assert!(layer.neuron(1).unwrap().is_trainable());
+
+    let inputs = [1.0];
+    layer.forward(&inputs);
+    let errors = layer.set_final_layer_output_errors(&[1.0, 1.0]).unwrap();
+    let expected = 1.0 - 0.5_f64.tanh().powi(2);
+    assert!((errors[0] - expected).abs() < 1e-12);
+    assert!((layer.input_errors()[0] - 2.0 * 0.5 * expected).abs() < 1e-12);
+
+    layer.step_gradient(&inputs);
+    assert_eq!(layer.neuron(0).unwrap().weights(), &[0.5]);
+    assert_eq!(layer.neuron(0).unwrap().bias(), 0.0);
+    assert!(layer.neuron(1).unwrap().weights()[0] < 0.5);
+
+    layer.set_neuron_trainable(0, true).unwrap();
+    layer.step_gradient(&inputs);
+    assert!(layer.neuron(0).unwrap().weights()[0] < 0.5);
 }
@@ -1,5 +1,4 @@
 use std::{
-    convert::identity,
     fs::File,
     io::{BufRead, BufReader},
 };
@@ -7,9 +6,10 @@ use std::{
 use rand::Rng;
 use rusty_network::{
     functions::{
-        activation_functions::{identity_prime, relu, relu_prime},
-        error_functions::squared_loss_prime,
+        activation_functions::ActivationFunction, error_functions::ErrorFunction,
+        weight_initializers::WeightInitializer,
     },
+    network_model::{NetworkBuilder, NetworkError, OutputHead},
     sequential::network::SequentialNetwork,
 };
 
@@ -35,9 +35,8 @@ fn test_zero_input_network() {
         &network_width_vec,
         second_random_integer,
         0.01,
-        relu,
-        relu_prime,
-        squared_loss_prime,
+        ActivationFunction::Relu,
+        ErrorFunction::SquaredLoss,
     ) {
         Ok(item) => item,
         Err(error) => panic!("{:#?}", error),
@@ -87,14 +86,14 @@ fn test_simple_dollar_architecture() {
     println!("Largura da rede: {:?}", network_width);
 
     let learning_rate = 0.01;
-    let mut new_network = SequentialNetwork::new(
+    let mut new_network = SequentialNetwork::new_with_seed(
         network_depth,
         &network_width,
         input_width,
         learning_rate,
-        identity,
-        identity_prime,
-        squared_loss_prime,
+        ActivationFunction::Identity,
+        ErrorFunction::SquaredLoss,
+        1,
     )
     .unwrap();
 
@@ -110,3 +109,790 @@ fn test_simple_dollar_architecture() {
     let final_result = test_return.unwrap();
     assert!(final_result < 26.0 && final_result > 24.0);
 }
+
+#[test]
+fn test_softmax_head_classifies_one_hot_inputs() {
+    let inputs = vec![
+        vec![1.0, 0.0, 0.0],
+        vec![0.0, 1.0, 0.0],
+        vec![0.0, 0.0, 1.0],
+    ];
+    let targets = vec![
+        vec![0.0, 1.0, 0.0],
+        vec![0.0, 0.0, 1.0],
+        vec![1.0, 0.0, 0.0],
+    ];
+
+    for output_head in [
+        OutputHead::Softmax(3),
+        OutputHead::SoftmaxWithErrorFunction(3),
+    ] {
+        let mut new_network = SequentialNetwork::from_builder(
+            NetworkBuilder::new(
+                1,
+                &[1],
+                3,
+                0.5,
+                ActivationFunction::Identity,
+                ErrorFunction::SquaredLoss,
+            )
+            .output_head(output_head)
+            .seed(11),
+        )
+        .unwrap();
+
+        for _ in 0..500 {
+            new_network
+                .iterations_train_vector(&inputs, &targets)
+                .unwrap();
+        }
+
+        for (input, target) in inputs.iter().zip(targets.iter()) {
+            let probabilities = new_network.predict_vector(input).unwrap();
+            assert!((probabilities.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+            let predicted_class = probabilities.iter().enumerate().fold(0, |best, (i, p)| {
+                if *p > probabilities[best] {
+                    i
+                } else {
+                    best
+                }
+            });
+            assert_eq!(target[predicted_class], 1.0);
+        }
+    }
+
+    for output_head in [
+        OutputHead::Softmax(0),
+        OutputHead::Softmax(1),
+        OutputHead::SoftmaxWithErrorFunction(0),
+        OutputHead::SoftmaxWithErrorFunction(1),
+    ] {
+        let classes = output_head.output_width();
+        let invalid_head = SequentialNetwork::from_builder(
+            NetworkBuilder::new(
+                1,
+                &[1],
+                3,
+                0.5,
+                ActivationFunction::Identity,
+                ErrorFunction::SquaredLoss,
+            )
+            .output_head(output_head),
+        );
+        assert_eq!(
+            invalid_head.err().map(|error| error.to_string()),
+            Some(NetworkError::InvalidClassCount(classes).to_string())
+        );
+    }
+}
+
+#[test]
+fn test_layer_activations_are_applied_per_layer() {
+    let layer_activations = [
+        ActivationFunction::Relu,
+        ActivationFunction::Tanh,
+        ActivationFunction::Identity,
+    ];
+    let builder = NetworkBuilder::new(
+        3,
+        &[2, 2],
+        2,
+        0.01,
+        ActivationFunction::Identity,
+        ErrorFunction::SquaredLoss,
+    );
+    let new_network =
+        SequentialNetwork::from_builder(builder.clone().layer_activations(&layer_activations))
+            .unwrap();
+
+    let description = format!("{}", new_network);
+    let relu_position = description.find("Layer (relu)").unwrap();
+    let tanh_position = description.find("Layer (tanh)").unwrap();
+    let identity_position = description.find("Layer (identity)").unwrap();
+    assert!(relu_position < tanh_position && tanh_position < identity_position);
+
+    let wrong_length =
+        SequentialNetwork::from_builder(builder.layer_activations(&layer_activations[..2]));
+    assert!(wrong_length.is_err());
+}
+
+#[test]
+fn test_sigmoid_output_activation_is_honored() {
+    let inputs = vec![vec![-2.0], vec![-1.0], vec![1.0], vec![2.0]];
+    let targets = vec![0.0, 0.0, 1.0, 1.0];
+
+    let mut new_network = SequentialNetwork::from_builder(
+        NetworkBuilder::new(
+            1,
+            &[1],
+            1,
+            0.5,
+            ActivationFunction::Relu,
+            ErrorFunction::SquaredLoss,
+        )
+        .output_activation(ActivationFunction::Sigmoid)
+        .seed(12),
+    )
+    .unwrap();
+
+    for _ in 0..1000 {
+        new_network.iterations_train(&inputs, &targets).unwrap();
+    }
+
+    for (input, target) in inputs.iter().zip(targets) {
+        let probability = new_network.predict_batch(input).unwrap();
+        let logit = new_network.predict_batch_no_activation(input).unwrap();
+        assert!(probability > 0.0 && probability < 1.0);
+        assert!((probability - target).abs() < 0.2);
+        assert!((probability - 1.0 / (1.0 + (-logit).exp())).abs() < 1e-12);
+    }
+}
+
+#[test]
+fn test_scalar_prediction_rejects_vector_heads() {
+    let mut new_network = SequentialNetwork::from_builder(
+        NetworkBuilder::new(
+            1,
+            &[1],
+            2,
+            0.01,
+            ActivationFunction::Identity,
+            ErrorFunction::SquaredLoss,
+        )
+        .output_head(OutputHead::Softmax(3)),
+    )
+    .unwrap();
+
+    assert!(new_network.predict_batch(&[1.0, 1.0]).is_err());
+    assert_eq!(new_network.predict_vector(&[1.0, 1.0]).unwrap().len(), 3);
+}
+
+#[test]
+fn test_training_reports_true_loss() {
+    let inputs = vec![vec![1.0], vec![2.0], vec![3.0]];
+    let targets = vec![2.0, 4.0, 6.0];
+
+    let mut new_network = SequentialNetwork::new_with_seed(
+        1,
+        &[1],
+        1,
+        0.02,
+        ActivationFunction::Identity,
+        ErrorFunction::SquaredLoss,
+        2,
+    )
+    .unwrap();
+
+    let first_prediction = new_network.predict_batch(&inputs[0]).unwrap();
+    let first_report = new_network.iterations_train(&inputs, &targets).unwrap();
+    assert_eq!(first_report.sample_losses.len(), 3);
+    assert!((first_report.sample_losses[0] - (2.0 - first_prediction).powi(2)).abs() < 1e-12);
+
+    let mut last_report = first_report.clone();
+    for _ in 0..200 {
+        last_report = new_network.iterations_train(&inputs, &targets).unwrap();
+    }
+    assert!(last_report.mean_loss() < first_report.mean_loss());
+    assert!(last_report.mean_loss() < 1e-3);
+}
+
+#[test]
+fn test_cross_entropy_classifiers() {
+    let inputs = vec![
+        vec![-2.0, 1.0],
+        vec![-1.0, 0.5],
+        vec![1.0, -0.5],
+        vec![2.0, -1.0],
+    ];
+    let targets = vec![0.0, 0.0, 1.0, 1.0];
+
+    let mut logit_network = SequentialNetwork::new_with_seed(
+        1,
+        &[1],
+        2,
+        0.1,
+        ActivationFunction::Identity,
+        ErrorFunction::BinaryCrossEntropyWithLogits,
+        3,
+    )
+    .unwrap();
+    let mut probability_network = SequentialNetwork::from_builder(
+        NetworkBuilder::new(
+            1,
+            &[1],
+            2,
+            0.1,
+            ActivationFunction::Identity,
+            ErrorFunction::BinaryCrossEntropy,
+        )
+        .output_activation(ActivationFunction::Sigmoid)
+        .seed(13),
+    )
+    .unwrap();
+
+    for _ in 0..300 {
+        logit_network.iterations_train(&inputs, &targets).unwrap();
+        probability_network
+            .iterations_train(&inputs, &targets)
+            .unwrap();
+    }
+
+    for (input, target) in inputs.iter().zip(targets.iter()) {
+        let logit = logit_network.predict_batch(input).unwrap();
+        assert_eq!(logit > 0.0, *target == 1.0);
+        let probability = probability_network.predict_batch(input).unwrap();
+        assert!((probability - target).abs() < 0.2);
+    }
+
+    let one_hot_targets = targets
+        .iter()
+        .map(|target| vec![1.0 - target, *target])
+        .collect::<Vec<Vec<f64>>>();
+    let mut categorical_network = SequentialNetwork::from_builder(
+        NetworkBuilder::new(
+            1,
+            &[1],
+            2,
+            0.1,
+            ActivationFunction::Identity,
+            ErrorFunction::CategoricalCrossEntropy,
+        )
+        .output_head(OutputHead::SoftmaxWithErrorFunction(2))
+        .seed(14),
+    )
+    .unwrap();
+
+    let first_report = categorical_network
+        .iterations_train_vector(&inputs, &one_hot_targets)
+        .unwrap();
+    let mut last_report = first_report.clone();
+    for _ in 0..300 {
+        last_report = categorical_network
+            .iterations_train_vector(&inputs, &one_hot_targets)
+            .unwrap();
+    }
+    assert!(last_report.mean_loss() < first_report.mean_loss());
+    assert!(last_report.mean_loss() < 0.1);
+}
+
+#[test]
+fn test_robust_losses_resist_outliers() {
+    let inputs = (1..=10)
+        .map(|x| vec![x as f64 / 10.0])
+        .collect::<Vec<Vec<f64>>>();
+    let mut targets = inputs.iter().map(|x| 2.0 * x[0]).collect::<Vec<f64>>();
+    targets[9] = 50.0;
+
+    let fitted_slope = |error_function: ErrorFunction| -> f64 {
+        let mut new_network = SequentialNetwork::new_with_seed(
+            1,
+            &[1],
+            1,
+            0.01,
+            ActivationFunction::Identity,
+            error_function,
+            4,
+        )
+        .unwrap();
+        for _ in 0..2000 {
+            new_network.iterations_train(&inputs, &targets).unwrap();
+        }
+        new_network.predict_batch(&[1.0]).unwrap() - new_network.predict_batch(&[0.0]).unwrap()
+    };
+
+    let squared_slope = fitted_slope(ErrorFunction::SquaredLoss);
+    for error_function in [
+        ErrorFunction::Huber(0.5),
+        ErrorFunction::MeanAbsoluteError,
+        ErrorFunction::LogCosh,
+    ] {
+        let robust_slope = fitted_slope(error_function.clone());
+        assert!(
+            (robust_slope - 2.0).abs() < (squared_slope - 2.0).abs(),
+            "{} slope {} vs squared loss slope {}",
+            error_function,
+            robust_slope,
+            squared_slope
+        );
+    }
+}
+
+#[test]
+fn test_single_neuron_margin_classifier() {
+    let inputs = vec![
+        vec![-2.0, 1.0],
+        vec![-1.0, 0.5],
+        vec![-1.5, -0.5],
+        vec![1.0, -0.5],
+        vec![2.0, -1.0],
+        vec![1.5, 0.5],
+    ];
+    let targets = vec![-1.0, -1.0, -1.0, 1.0, 1.0, 1.0];
+
+    for error_function in [ErrorFunction::Hinge, ErrorFunction::SquaredHinge] {
+        let mut new_network = SequentialNetwork::new_with_seed(
+            1,
+            &[1],
+            2,
+            0.05,
+            ActivationFunction::Identity,
+            error_function,
+            5,
+        )
+        .unwrap();
+
+        let mut last_report = new_network.iterations_train(&inputs, &targets).unwrap();
+        for _ in 0..300 {
+            last_report = new_network.iterations_train(&inputs, &targets).unwrap();
+        }
+
+        assert!(last_report.mean_loss() < 0.1);
+        for (input, target) in inputs.iter().zip(targets.iter()) {
+            let margin = new_network.predict_batch(input).unwrap();
+            assert_eq!(margin.signum(), *target);
+        }
+    }
+}
+
+#[test]
+fn test_quantile_head_predicts_intervals() {
+    let quantiles = vec![0.1, 0.5, 0.9];
+    let inputs = vec![vec![1.0]; 100];
+    // Visit the evenly spread targets out of order, so no single epoch drifts upwards.
+    let targets = (0..100)
+        .map(|i| (i * 37 % 100) as f64 / 100.0)
+        .collect::<Vec<f64>>();
+
+    let mut new_network = SequentialNetwork::from_builder(
+        NetworkBuilder::new(
+            1,
+            &[1],
+            1,
+            0.005,
+            ActivationFunction::Identity,
+            ErrorFunction::SquaredLoss,
+        )
+        .output_head(OutputHead::Quantiles(quantiles.clone()))
+        .seed(15),
+    )
+    .unwrap();
+
+    for _ in 0..300 {
+        new_network.iterations_train(&inputs, &targets).unwrap();
+    }
+
+    let predictions = new_network.predict_vector(&[1.0]).unwrap();
+    assert_eq!(predictions.len(), quantiles.len());
+    for (prediction, quantile) in predictions.iter().zip(quantiles.iter()) {
+        assert!((prediction - quantile).abs() < 0.05);
+    }
+    assert!(predictions[0] < predictions[1] && predictions[1] < predictions[2]);
+
+    for (quantiles, expected_error) in [
+        (vec![0.5, 1.0], NetworkError::InvalidQuantile(1.0)),
+        (vec![], NetworkError::InvalidQuantileList),
+        (vec![0.9, 0.1], NetworkError::InvalidQuantileList),
+        (vec![0.5, 0.5], NetworkError::InvalidQuantileList),
+    ] {
+        let invalid_quantiles = SequentialNetwork::from_builder(
+            NetworkBuilder::new(
+                1,
+                &[1],
+                1,
+                0.005,
+                ActivationFunction::Identity,
+                ErrorFunction::SquaredLoss,
+            )
+            .output_head(OutputHead::Quantiles(quantiles)),
+        );
+        assert_eq!(
+            invalid_quantiles.err().map(|error| error.to_string()),
+            Some(expected_error.to_string())
+        );
+    }
+}
+
+#[test]
+fn test_invalid_error_functions_are_rejected() {
+    for delta in [-1.0, f64::NAN] {
+        let invalid_network = SequentialNetwork::new(
+            1,
+            &[1],
+            1,
+            0.1,
+            ActivationFunction::Identity,
+            ErrorFunction::Huber(delta),
+        );
+        assert_eq!(
+            invalid_network.err().map(|error| error.to_string()),
+            Some(NetworkError::InvalidHuberDelta(delta).to_string())
+        );
+    }
+}
+
+#[test]
+fn test_gaussian_head_predicts_uncertainty() {
+    // Both groups alternate one standard deviation either side of their mean.
+    let mut inputs = Vec::new();
+    let mut targets = Vec::new();
+    for i in 0..40 {
+        let sign = if i % 2 == 0 { 1.0 } else { -1.0 };
+        inputs.push(vec![0.0]);
+        targets.push(1.0 + 0.2 * sign);
+        inputs.push(vec![1.0]);
+        targets.push(3.0 + 1.0 * sign);
+    }
+
+    let mut new_network = SequentialNetwork::from_builder(
+        NetworkBuilder::new(
+            1,
+            &[1],
+            1,
+            0.005,
+            ActivationFunction::Identity,
+            ErrorFunction::SquaredLoss,
+        )
+        .output_head(OutputHead::Gaussian)
+        .seed(16),
+    )
+    .unwrap();
+
+    let first_report = new_network.iterations_train(&inputs, &targets).unwrap();
+    let mut last_report = first_report.clone();
+    for _ in 0..500 {
+        last_report = new_network.iterations_train(&inputs, &targets).unwrap();
+    }
+    assert!(last_report.mean_loss() < first_report.mean_loss());
+
+    let narrow = new_network.predict_vector(&[0.0]).unwrap();
+    let wide = new_network.predict_vector(&[1.0]).unwrap();
+    assert!((narrow[0] - 1.0).abs() < 0.1);
+    assert!((wide[0] - 3.0).abs() < 0.3);
+    assert!((narrow[1] - 0.04).abs() < 0.02);
+    assert!((wide[1] - 1.0).abs() < 0.3);
+}
+
+#[test]
+fn test_closure_activation_and_loss_train_network() {
+    let inputs = vec![vec![-2.0], vec![-1.0], vec![1.0], vec![2.0]];
+    // Reachable as runtime_leaky_relu(2x).
+    let targets = vec![-2.0, -1.0, 2.0, 4.0];
+
+    let slope = 0.5;
+    let positive_weight = 2.0;
+    let activation = ActivationFunction::from_closures(
+        "runtime_leaky_relu",
+        move |value| if value > 0.0 { value } else { slope * value },
+        move |value| if value > 0.0 { 1.0 } else { slope },
+    );
+    let error_function = ErrorFunction::from_closures(
+        "weighted_squared_loss",
+        move |aim: f64, final_answer: f64| {
+            let weight = if aim > 0.0 { positive_weight } else { 1.0 };
+            weight * (aim - final_answer).powi(2)
+        },
+        move |aim: f64, final_answer: f64| {
+            let weight = if aim > 0.0 { positive_weight } else { 1.0 };
+            -2.0 * weight * (aim - final_answer)
+        },
+    );
+
+    let mut new_network =
+        SequentialNetwork::new_with_seed(1, &[1], 1, 0.02, activation, error_function, 6).unwrap();
+    assert!(format!("{}", new_network).contains("Layer (runtime_leaky_relu)"));
+
+    let first_report = new_network.iterations_train(&inputs, &targets).unwrap();
+    let mut last_report = first_report.clone();
+    for _ in 0..300 {
+        last_report = new_network.iterations_train(&inputs, &targets).unwrap();
+    }
+    assert!(last_report.mean_loss() < first_report.mean_loss());
+    assert!(last_report.mean_loss() < 1e-3);
+}
+
+#[test]
+fn test_focal_loss_learns_minority_class() {
+    let mut inputs = (0..38)
+        .map(|i| vec![-1.0 + i as f64 / 20.0])
+        .collect::<Vec<Vec<f64>>>();
+    let mut targets = vec![0.0; inputs.len()];
+    inputs.extend([vec![1.2], vec![1.4]]);
+    targets.extend([1.0, 1.0]);
+
+    let mut new_network = SequentialNetwork::from_builder(
+        NetworkBuilder::new(
+            1,
+            &[1],
+            1,
+            0.5,
+            ActivationFunction::Identity,
+            ErrorFunction::Focal {
+                gamma: 2.0,
+                alpha: 0.75,
+            },
+        )
+        .output_activation(ActivationFunction::Sigmoid)
+        .seed(17),
+    )
+    .unwrap();
+
+    let first_report = new_network.iterations_train(&inputs, &targets).unwrap();
+    let mut last_report = first_report.clone();
+    for _ in 0..500 {
+        last_report = new_network.iterations_train(&inputs, &targets).unwrap();
+    }
+    assert!(last_report.mean_loss() < first_report.mean_loss());
+
+    for (input, target) in inputs.iter().zip(targets.iter()) {
+        let probability = new_network.predict_batch(input).unwrap();
+        assert_eq!(
+            probability > 0.5,
+            *target == 1.0,
+            "{:?} {}",
+            input,
+            probability
+        );
+    }
+}
+
+#[test]
+fn test_sample_weights_scale_each_sample() {
+    // The same input carries conflicting labels, so the fit lands on their weighted mean.
+    let inputs = vec![vec![1.0], vec![1.0]];
+    let targets = vec![0.0, 1.0];
+    let sample_weights = vec![1.0, 3.0];
+
+    let mut new_network = SequentialNetwork::new_with_seed(
+        1,
+        &[1],
+        1,
+        0.002,
+        ActivationFunction::Identity,
+        ErrorFunction::SquaredLoss,
+        7,
+    )
+    .unwrap();
+
+    let first_prediction = new_network.predict_batch(&inputs[0]).unwrap();
+    let report = new_network
+        .iterations_train_weighted(&inputs, &targets, &sample_weights)
+        .unwrap();
+    assert!((report.sample_losses[0] - first_prediction.powi(2)).abs() < 1e-12);
+
+    for _ in 0..500 {
+        new_network
+            .iterations_train_weighted(&inputs, &targets, &sample_weights)
+            .unwrap();
+    }
+    assert!((new_network.predict_batch(&inputs[0]).unwrap() - 0.75).abs() < 0.02);
+
+    assert!(matches!(
+        new_network.iterations_train_weighted(&inputs, &targets, &[1.0]),
+        Err(NetworkError::SampleWeightsIncompatibleLength(1, 2))
+    ));
+    assert!(matches!(
+        new_network.batch_train_weighted(&inputs, &targets, &[1.0, 2.0, 3.0]),
+        Err(NetworkError::SampleWeightsIncompatibleLength(3, 2))
+    ));
+}
+
+#[test]
+fn test_seeded_networks_are_reproducible() {
+    let inputs = vec![vec![0.5, -1.0], vec![-0.3, 0.8], vec![1.2, 0.1]];
+    let targets = vec![0.2, -0.4, 0.9];
+
+    let seeded_run = |seed: u64| -> (String, Vec<f64>) {
+        let mut new_network = SequentialNetwork::from_builder(
+            NetworkBuilder::new(
+                3,
+                &[4, 3],
+                2,
+                0.05,
+                ActivationFunction::Identity,
+                ErrorFunction::SquaredLoss,
+            )
+            .layer_activations(&[
+                ActivationFunction::Tanh,
+                ActivationFunction::Relu,
+                ActivationFunction::Identity,
+            ])
+            .layer_initializers(&[
+                WeightInitializer::XavierNormal,
+                WeightInitializer::Orthogonal,
+                WeightInitializer::HeUniform,
+            ])
+            .seed(seed),
+        )
+        .unwrap();
+        let mut losses = Vec::new();
+        for _ in 0..20 {
+            losses.extend(
+                new_network
+                    .iterations_train(&inputs, &targets)
+                    .unwrap()
+                    .sample_losses,
+            );
+        }
+        (format!("{}", new_network), losses)
+    };
+
+    assert_eq!(seeded_run(7), seeded_run(7));
+    assert_ne!(seeded_run(7).1, seeded_run(8).1);
+
+    let first = SequentialNetwork::new_with_seed(
+        2,
+        &[3],
+        2,
+        0.01,
+        ActivationFunction::Relu,
+        ErrorFunction::SquaredLoss,
+        11,
+    );
+    let second = SequentialNetwork::new_with_seed(
+        2,
+        &[3],
+        2,
+        0.01,
+        ActivationFunction::Relu,
+        ErrorFunction::SquaredLoss,
+        11,
+    );
+    assert_eq!(
+        first.unwrap().predict_vector(&[0.4, -0.2]).unwrap(),
+        second.unwrap().predict_vector(&[0.4, -0.2]).unwrap()
+    );
+
+    let gaussian_network = || {
+        SequentialNetwork::from_builder(
+            NetworkBuilder::new(
+                2,
+                &[3],
+                2,
+                0.01,
+                ActivationFunction::Tanh,
+                ErrorFunction::SquaredLoss,
+            )
+            .output_head(OutputHead::Gaussian)
+            .layer_initializers(&[
+                WeightInitializer::XavierUniform,
+                WeightInitializer::HeNormal,
+            ])
+            .seed(11),
+        )
+        .unwrap()
+    };
+    assert_eq!(
+        gaussian_network().predict_vector(&[0.4, -0.2]).unwrap(),
+        gaussian_network().predict_vector(&[0.4, -0.2]).unwrap()
+    );
+}
+
+#[test]
+fn test_batch_training_averages_sample_gradients() {
+    let inputs = vec![vec![-1.0], vec![0.0], vec![1.0], vec![2.0]];
+    let targets = vec![-1.0, 1.0, 3.0, 5.0];
+
+    let mut new_network = SequentialNetwork::new_with_seed(
+        1,
+        &[1],
+        1,
+        0.1,
+        ActivationFunction::Identity,
+        ErrorFunction::SquaredLoss,
+        21,
+    )
+    .unwrap();
+
+    let first_report = new_network.batch_train(&inputs, &targets).unwrap();
+    let mut last_report = first_report.clone();
+    for _ in 0..300 {
+        last_report = new_network.batch_train(&inputs, &targets).unwrap();
+    }
+    assert!(last_report.mean_loss() < 1e-6);
+    assert!((new_network.predict_batch(&[3.0]).unwrap() - 7.0).abs() < 1e-3);
+}
+
+#[test]
+fn test_frozen_layers_keep_their_weights() {
+    let inputs = vec![vec![0.5, -1.0], vec![-0.3, 0.8], vec![1.2, 0.1]];
+    let targets = vec![0.2, -0.4, 0.9];
+
+    let mut new_network = SequentialNetwork::new_with_seed(
+        3,
+        &[3, 3],
+        2,
+        0.05,
+        ActivationFunction::Tanh,
+        ErrorFunction::SquaredLoss,
+        31,
+    )
+    .unwrap();
+    new_network.set_layer_trainable(0, false).unwrap();
+    new_network.set_layer_trainable(1, false).unwrap();
+    assert!(new_network.set_layer_trainable(3, false).is_err());
+
+    let weights_by_layer = |network: &mut SequentialNetwork| -> Vec<Vec<Vec<f64>>> {
+        (0..3)
+            .map(|index| network.layer_mut(index).unwrap().get_weights_by_neurons())
+            .collect()
+    };
+    let initial_weights = weights_by_layer(&mut new_network);
+
+    let first_report = new_network.iterations_train(&inputs, &targets).unwrap();
+    let mut last_report = first_report.clone();
+    for _ in 0..100 {
+        last_report = new_network.iterations_train(&inputs, &targets).unwrap();
+        new_network.batch_train(&inputs, &targets).unwrap();
+    }
+    let trained_weights = weights_by_layer(&mut new_network);
+
+    assert_eq!(trained_weights[0], initial_weights[0]);
+    assert_eq!(trained_weights[1], initial_weights[1]);
+    assert_ne!(trained_weights[2], initial_weights[2]);
+    assert!(last_report.mean_loss() < first_report.mean_loss());
+}
+
+#[test]
+fn test_layers_expose_the_last_forward_pass() {
+    let inputs = [0.4, -0.7];
+
+    let mut new_network = SequentialNetwork::new_with_seed(
+        3,
+        &[3, 2],
+        2,
+        0.05,
+        ActivationFunction::Relu,
+        ErrorFunction::SquaredLoss,
+        17,
+    )
+    .unwrap();
+    let outputs = new_network.predict_vector(&inputs).unwrap();
+
+    let layers = new_network.layers();
+    assert_eq!(layers.len(), 3);
+    assert!(new_network.layer(3).is_err());
+
+    let mut layer_inputs = inputs.to_vec();
+    for (index, layer) in layers.iter().enumerate() {
+        assert!(std::ptr::eq(*layer, new_network.layer(index).unwrap()));
+        assert_eq!(layer.last_outputs(), layer.compute_m_to_n(&layer_inputs));
+        assert_eq!(
+            layer.last_pre_activations(),
+            layer.compute_m_to_n_without_activation(&layer_inputs)
+        );
+        for neuron in layer.neurons() {
+            assert_eq!(neuron.last_output(), neuron.last_pre_activation().max(0.0));
+        }
+        layer_inputs = layer.last_outputs().to_vec();
+    }
+    assert_eq!(layers[2].last_outputs(), outputs);
+
+    assert!(layers[1].current_errors().iter().all(|error| *error == 0.0));
+    new_network
+        .iterations_train(&[inputs.to_vec()], &[5.0])
+        .unwrap();
+    let output_neuron = new_network.layer(2).unwrap().neuron(0).unwrap();
+    assert!(output_neuron.current_error() < 0.0);
+    assert_eq!(output_neuron.learning_rate(), 0.05);
+    assert!(new_network.layer(2).unwrap().neuron(1).is_err());
+}
@@ -0,0 +1,61 @@
+//! The comparisons against the scalar loops only mean something with the vectorized ones
+//! compiled in, so they run under `cargo test --features simd`.
+
+#[cfg(feature = "simd")]
+use rand::{rngs::StdRng, Rng, SeedableRng};
+#[cfg(feature = "simd")]
+use rusty_network::vector_math::{
+    dot, dot_scalar, scaled_add, scaled_add_scalar, DOT_PRODUCT_TOLERANCE,
+};
+
+#[cfg(feature = "simd")]
+fn random_vector(rng: &mut StdRng, length: usize) -> Vec<f64> {
+    (0..length).map(|_| rng.gen_range(-1e3..1e3)).collect()
+}
+
+#[cfg(feature = "simd")]
+#[test]
+fn test_dot_matches_scalar_within_tolerance() {
+    let mut rng = StdRng::seed_from_u64(3);
+
+    for length in 0..70 {
+        let (first, second) = (
+            random_vector(&mut rng, length),
+            random_vector(&mut rng, length),
+        );
+        let magnitude = first
+            .iter()
+            .zip(&second)
+            .map(|(a, b)| (a * b).abs())
+            .sum::<f64>();
+        let difference = (dot(&first, &second) - dot_scalar(&first, &second)).abs();
+        assert!(difference <= DOT_PRODUCT_TOLERANCE * length as f64 * magnitude);
+    }
+
+    // Mismatched lengths use the shorter one, like `zip`.
+    assert_eq!(dot(&[1.0, 2.0, 3.0, 4.0, 5.0], &[1.0; 3]), 6.0);
+}
+
+#[cfg(feature = "simd")]
+#[test]
+fn test_scaled_add_matches_scalar_exactly() {
+    let mut rng = StdRng::seed_from_u64(5);
+
+    for length in 0..70 {
+        let values = random_vector(&mut rng, length);
+        let mut target = random_vector(&mut rng, length);
+        let mut expected = target.clone();
+        scaled_add(&mut target, 0.37, &values);
+        scaled_add_scalar(&mut expected, 0.37, &values);
+        assert_eq!(target, expected);
+    }
+}
+
+#[cfg(not(feature = "simd"))]
+#[test]
+fn test_scalar_dot_folds_from_the_initial_value() {
+    use rusty_network::vector_math::dot_from;
+
+    // Each unit term rounds away against 1e16, where their sum added at once would not.
+    assert_eq!(dot_from(1e16, &[1.0, 1.0], &[1.0, 1.0]), 1e16);
+}
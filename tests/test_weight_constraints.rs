@@ -0,0 +1,136 @@
+use rusty_network::{
+    functions::{
+        activation_functions::ActivationFunction, error_functions::ErrorFunction,
+        weight_constraints::WeightConstraint, weight_initializers::WeightInitializer,
+    },
+    layer::Layer,
+    network_model::NetworkError,
+    sequential::network::SequentialNetwork,
+};
+
+#[test]
+fn test_constraints_bound_weights() {
+    let mut weights = [3.0, -4.0];
+    WeightConstraint::MaxNorm(1.0).apply(&mut weights);
+    assert!((weights[0] - 0.6).abs() < 1e-12 && (weights[1] + 0.8).abs() < 1e-12);
+
+    let mut weights = [0.3, -0.4];
+    WeightConstraint::MaxNorm(1.0).apply(&mut weights);
+    assert_eq!(weights, [0.3, -0.4]);
+
+    let mut weights = [0.3, -0.4];
+    WeightConstraint::NonNegative.apply(&mut weights);
+    assert_eq!(weights, [0.3, 0.0]);
+
+    let mut weights = [2.0, -0.1, -3.0];
+    WeightConstraint::Clip {
+        min: -0.5,
+        max: 0.5,
+    }
+    .apply(&mut weights);
+    assert_eq!(weights, [0.5, -0.1, -0.5]);
+}
+
+#[test]
+fn test_constraints_are_reapplied_after_each_step() {
+    let mut layer = Layer::new(
+        1,
+        1,
+        1.0,
+        ActivationFunction::Identity,
+        &WeightInitializer::Constant(0.5),
+        &mut rand::thread_rng(),
+    );
+    layer
+        .set_weight_constraints(&[WeightConstraint::NonNegative])
+        .unwrap();
+
+    // A positive error on a positive input drives the weight below zero.
+    layer.set_final_layer_errors(&[2.0]).unwrap();
+    layer.step_gradient(&[1.0]);
+    assert_eq!(layer.weights(), &[0.0]);
+    assert_eq!(layer.get_biases(), &[-2.0]);
+}
+
+#[test]
+fn test_network_layers_keep_their_constraints() {
+    // The target falls with the first input, which non-negative weights cannot follow.
+    let inputs = vec![
+        vec![1.0, 0.0],
+        vec![0.0, 1.0],
+        vec![2.0, 1.0],
+        vec![1.0, 2.0],
+    ];
+    let targets = vec![-1.0, 1.0, -1.0, 1.0];
+
+    let mut new_network = SequentialNetwork::new_with_seed(
+        2,
+        &[3],
+        2,
+        0.05,
+        ActivationFunction::Identity,
+        ErrorFunction::SquaredLoss,
+        41,
+    )
+    .unwrap();
+    new_network
+        .set_layer_weight_constraints(0, &[WeightConstraint::MaxNorm(0.5)])
+        .unwrap();
+    new_network
+        .set_layer_weight_constraints(1, &[WeightConstraint::NonNegative])
+        .unwrap();
+
+    for _ in 0..200 {
+        new_network.iterations_train(&inputs, &targets).unwrap();
+    }
+
+    for weights in new_network.layer_mut(0).unwrap().get_weights_by_neurons() {
+        let norm = weights.iter().map(|weight| weight * weight).sum::<f64>();
+        assert!(norm.sqrt() <= 0.5 + 1e-12);
+    }
+    for weights in new_network.layer_mut(1).unwrap().get_weights_by_neurons() {
+        assert!(weights.iter().all(|weight| *weight >= 0.0));
+    }
+}
+
+#[test]
+fn test_invalid_constraints_are_rejected() {
+    let mut new_network = SequentialNetwork::new_with_seed(
+        2,
+        &[3],
+        2,
+        0.05,
+        ActivationFunction::Identity,
+        ErrorFunction::SquaredLoss,
+        43,
+    )
+    .unwrap();
+    let weights = new_network.layer_mut(0).unwrap().get_weights_by_neurons();
+
+    for constraint in [
+        WeightConstraint::Clip { min: 1.0, max: 0.0 },
+        WeightConstraint::Clip {
+            min: f64::NAN,
+            max: 0.0,
+        },
+        WeightConstraint::Clip {
+            min: 0.0,
+            max: f64::NAN,
+        },
+        WeightConstraint::MaxNorm(-1.0),
+        WeightConstraint::MaxNorm(f64::NAN),
+        WeightConstraint::MaxNorm(f64::INFINITY),
+    ] {
+        assert!(matches!(
+            new_network
+                .set_layer_weight_constraints(0, &[WeightConstraint::NonNegative, constraint]),
+            Err(NetworkError::InvalidWeightConstraint(_))
+        ));
+    }
+
+    // Nothing rejected is kept or applied.
+    assert_eq!(
+        new_network.layer_mut(0).unwrap().get_weights_by_neurons(),
+        weights
+    );
+}
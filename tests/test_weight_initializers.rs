@@ -0,0 +1,149 @@
+use rusty_network::{
+    functions::{
+        activation_functions::ActivationFunction, error_functions::ErrorFunction,
+        weight_initializers::WeightInitializer,
+    },
+    network_model::{NetworkBuilder, NetworkError},
+    sequential::network::SequentialNetwork,
+};
+
+const NAMED_INITIALIZERS: [WeightInitializer; 9] = [
+    WeightInitializer::StandardUniform,
+    WeightInitializer::XavierUniform,
+    WeightInitializer::XavierNormal,
+    WeightInitializer::HeUniform,
+    WeightInitializer::HeNormal,
+    WeightInitializer::LeCunUniform,
+    WeightInitializer::LeCunNormal,
+    WeightInitializer::Orthogonal,
+    WeightInitializer::Zeros,
+];
+
+fn standard_deviation(weights: &[Vec<f64>]) -> f64 {
+    let values = weights.iter().flatten().collect::<Vec<&f64>>();
+    let mean = values.iter().cloned().sum::<f64>() / values.len() as f64;
+    (values
+        .iter()
+        .map(|value| (*value - mean).powi(2))
+        .sum::<f64>()
+        / values.len() as f64)
+        .sqrt()
+}
+
+#[test]
+fn test_initializer_name_round_trip() {
+    for initializer in NAMED_INITIALIZERS {
+        assert_eq!(
+            WeightInitializer::from_name(initializer.name()),
+            Some(initializer)
+        );
+    }
+    let constant = WeightInitializer::Constant(0.3);
+    assert_eq!(
+        WeightInitializer::from_name(&constant.to_string()),
+        Some(constant)
+    );
+    assert_eq!(WeightInitializer::from_name("constant"), None);
+}
+
+#[test]
+fn test_initializers_shape_and_scale() {
+    let mut rng = rand::thread_rng();
+    let (fan_in, fan_out) = (200, 100);
+
+    for initializer in NAMED_INITIALIZERS {
+        let (weights, biases) = initializer.initialize(&mut rng, fan_in, fan_out);
+        assert_eq!(weights.len(), fan_out);
+        assert!(weights.iter().all(|neuron| neuron.len() == fan_in));
+        assert_eq!(biases.len(), fan_out);
+        if initializer != WeightInitializer::StandardUniform {
+            assert!(biases.iter().all(|bias| *bias == 0.0));
+        }
+    }
+
+    let expected_deviations = [
+        (WeightInitializer::XavierUniform, (2.0 / 300.0_f64).sqrt()),
+        (WeightInitializer::XavierNormal, (2.0 / 300.0_f64).sqrt()),
+        (WeightInitializer::HeUniform, (2.0 / 200.0_f64).sqrt()),
+        (WeightInitializer::HeNormal, (2.0 / 200.0_f64).sqrt()),
+        (WeightInitializer::LeCunUniform, (1.0 / 200.0_f64).sqrt()),
+        (WeightInitializer::LeCunNormal, (1.0 / 200.0_f64).sqrt()),
+    ];
+    for (initializer, expected_deviation) in expected_deviations {
+        let (weights, _) = initializer.initialize(&mut rng, fan_in, fan_out);
+        let deviation = standard_deviation(&weights);
+        assert!(
+            (deviation / expected_deviation - 1.0).abs() < 0.05,
+            "{} deviation {} expected {}",
+            initializer,
+            deviation,
+            expected_deviation
+        );
+    }
+}
+
+#[test]
+fn test_orthogonal_initializer_is_orthonormal() {
+    let mut rng = rand::thread_rng();
+
+    for (fan_in, fan_out) in [(6, 4), (4, 6), (5, 5)] {
+        let (weights, _) = WeightInitializer::Orthogonal.initialize(&mut rng, fan_in, fan_out);
+        // Rows are orthonormal for wide layers, columns for tall ones.
+        let vectors = match fan_out <= fan_in {
+            true => weights,
+            false => (0..fan_in)
+                .map(|column| weights.iter().map(|row| row[column]).collect())
+                .collect(),
+        };
+        for (i, first) in vectors.iter().enumerate() {
+            for (j, second) in vectors.iter().enumerate() {
+                let dot = first.iter().zip(second).map(|(a, b)| a * b).sum::<f64>();
+                let expected = if i == j { 1.0 } else { 0.0 };
+                assert!((dot - expected).abs() < 1e-10);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_constant_and_closure_initializers() {
+    let mut rng = rand::thread_rng();
+
+    let (weights, _) = WeightInitializer::Constant(0.3).initialize(&mut rng, 3, 2);
+    assert_eq!(weights, vec![vec![0.3; 3]; 2]);
+
+    let scale = 0.5;
+    let closure_initializer =
+        WeightInitializer::from_closure("scaled_fan_in", move |_, fan_in, _| scale / fan_in as f64);
+    let (weights, biases) = closure_initializer.initialize(&mut rng, 4, 3);
+    assert_eq!(weights, vec![vec![0.125; 4]; 3]);
+    assert_eq!(biases, vec![0.0; 3]);
+    assert_eq!(closure_initializer.name(), "scaled_fan_in");
+}
+
+#[test]
+fn test_network_uses_one_initializer_per_layer() {
+    let builder = NetworkBuilder::new(
+        2,
+        &[2],
+        2,
+        0.01,
+        ActivationFunction::Identity,
+        ErrorFunction::SquaredLoss,
+    );
+    let mut new_network = SequentialNetwork::from_builder(builder.clone().layer_initializers(&[
+        WeightInitializer::Constant(1.0),
+        WeightInitializer::Constant(0.5),
+    ]))
+    .unwrap();
+
+    // Hidden neurons each give 1 + 2, and the output neuron halves their sum.
+    assert_eq!(new_network.predict_batch(&[1.0, 2.0]).unwrap(), 3.0);
+
+    let wrong_length =
+        SequentialNetwork::from_builder(builder.layer_initializers(&[WeightInitializer::HeNormal]));
+    assert!(matches!(
+        wrong_length,
+        Err(NetworkError::IncorrectLayerInitializerList)
+    ));
+}
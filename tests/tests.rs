@@ -1,3 +1,9 @@
+mod test_activation_functions;
+mod test_autodiff;
+mod test_error_functions;
 mod test_layer;
 mod test_neuron;
 mod test_sequential_network;
+mod test_vector_math;
+mod test_weight_constraints;
+mod test_weight_initializers;